@@ -1,9 +1,14 @@
 // src/main.rs
 
 use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
-use namedivider_rs::divider::basic_name_divider::get_basic_name_divider;
-use namedivider_rs::divider::gbdt_name_divider::get_gbdt_name_divider;
+use namedivider_rs::divider::basic_name_divider::BasicNameDivider;
+use namedivider_rs::divider::gbdt_name_divider::GBDTNameDivider;
+use namedivider_rs::divider::divided_name::Span;
 use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::provenance::Provenance;
+use namedivider_rs::encodability::{
+    check_encodability, EncodabilityReport, EncodingTarget, VariantTable,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -30,6 +35,9 @@ struct DivisionRequest {
     names: Vec<String>,
     #[serde(default = "default_mode")]
     mode: String,
+    /// `cp932` or `jisx0208` to report chars the encoding cannot represent
+    #[serde(default)]
+    check_encoding: Option<String>,
 }
 
 fn default_mode() -> String {
@@ -48,6 +56,13 @@ struct ViewDividedName {
     separator: String,
     score: f64,
     algorithm: String,
+    provenance: Provenance,
+    normalized_family: String,
+    normalized_given: String,
+    family_span: Span,
+    given_span: Span,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encodability: Option<EncodabilityReport>,
 }
 
 async fn validate(division_request: &web::Json<DivisionRequest>) -> Result<(), HttpResponse> {
@@ -57,6 +72,11 @@ async fn validate(division_request: &web::Json<DivisionRequest>) -> Result<(), H
         return Err(HttpResponse::UnprocessableEntity()
             .json("You can only divide up to 1000 names at a time."));
     }
+    if let Some(check_encoding) = &division_request.check_encoding {
+        if let Err(why) = check_encoding.parse::<EncodingTarget>() {
+            return Err(HttpResponse::UnprocessableEntity().json(why));
+        }
+    }
     Ok(())
 }
 
@@ -77,15 +97,34 @@ async fn divide(
         &app_state.gbdt_divider
     };
     
+    let encoding_target = division_request
+        .check_encoding
+        .as_ref()
+        .map(|check_encoding| check_encoding.parse::<EncodingTarget>().unwrap());
+    let variant_table = VariantTable::default();
+
     let mut divided_names: Vec<ViewDividedName> = Vec::new();
     for name in &division_request.names {
-        let divided_name = divider.divide_name(name);
+        let divided_name = match divider.try_divide_name(name) {
+            Ok(divided_name) => divided_name,
+            Err(why) => {
+                return HttpResponse::UnprocessableEntity().json(format!("{}: {:?}", why, name))
+            }
+        };
+        let encodability = encoding_target
+            .map(|target| check_encodability(&divided_name, target, &variant_table));
         divided_names.push(ViewDividedName {
+            algorithm: divided_name.algorithm(),
             family: divided_name.family,
             given: divided_name.given,
             separator: divided_name.separator,
             score: divided_name.score,
-            algorithm: divided_name.algorithm,
+            provenance: divided_name.provenance,
+            normalized_family: divided_name.normalized_family,
+            normalized_given: divided_name.normalized_given,
+            family_span: divided_name.family_span,
+            given_span: divided_name.given_span,
+            encodability,
         });
     }
     let division_result = DivisionResult { divided_names };
@@ -95,17 +134,15 @@ async fn divide(
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let basic_divider = Arc::new(get_basic_name_divider(
+    let basic_divider = Arc::new(BasicNameDivider::new(
         " ".to_string(),
         true,
-        "kanji_feature".to_string(),
         false,
     )) as Arc<dyn NameDivider + Send + Sync>;
     
-    let gbdt_divider = Arc::new(get_gbdt_name_divider(
+    let gbdt_divider = Arc::new(GBDTNameDivider::new(
         " ".to_string(),
         true,
-        "gbdt".to_string(),
     )) as Arc<dyn NameDivider + Send + Sync>;
     
     let app_state = web::Data::new(AppState {
@@ -139,17 +176,15 @@ mod tests {
 
     #[actix_web::test]
     async fn test_divide_basic() {
-        let basic_divider = Arc::new(get_basic_name_divider(
+        let basic_divider = Arc::new(BasicNameDivider::new(
             " ".to_string(),
             true,
-            "kanji_feature".to_string(),
             false,
         )) as Arc<dyn NameDivider + Send + Sync>;
         
-        let gbdt_divider = Arc::new(get_gbdt_name_divider(
+        let gbdt_divider = Arc::new(GBDTNameDivider::new(
             " ".to_string(),
             true,
-            "gbdt".to_string(),
         )) as Arc<dyn NameDivider + Send + Sync>;
         
         let app_state = web::Data::new(AppState {
@@ -168,6 +203,7 @@ mod tests {
             .set_json(&DivisionRequest {
                 names: vec!["竈門炭治郎".to_string()],
                 mode: "basic".to_string(),
+                check_encoding: None,
             })
             .to_request();
 
@@ -177,17 +213,15 @@ mod tests {
 
     #[actix_web::test]
     async fn test_divide_validation_error() {
-        let basic_divider = Arc::new(get_basic_name_divider(
+        let basic_divider = Arc::new(BasicNameDivider::new(
             " ".to_string(),
             true,
-            "kanji_feature".to_string(),
             false,
         )) as Arc<dyn NameDivider + Send + Sync>;
         
-        let gbdt_divider = Arc::new(get_gbdt_name_divider(
+        let gbdt_divider = Arc::new(GBDTNameDivider::new(
             " ".to_string(),
             true,
-            "gbdt".to_string(),
         )) as Arc<dyn NameDivider + Send + Sync>;
         
         let app_state = web::Data::new(AppState {
@@ -206,10 +240,63 @@ mod tests {
             .set_json(&DivisionRequest {
                 names: vec!["竈門炭治郎".to_string()],
                 mode: "invalid".to_string(),
+                check_encoding: None,
             })
             .to_request();
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), 422);
     }
+
+    #[actix_web::test]
+    async fn test_divide_with_encoding_check() {
+        let basic_divider = Arc::new(BasicNameDivider::new(
+            " ".to_string(),
+            true,
+            false,
+        )) as Arc<dyn NameDivider + Send + Sync>;
+
+        let gbdt_divider = Arc::new(GBDTNameDivider::new(
+            " ".to_string(),
+            true,
+        )) as Arc<dyn NameDivider + Send + Sync>;
+
+        let app_state = web::Data::new(AppState {
+            basic_divider,
+            gbdt_divider,
+        });
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .service(divide)
+        ).await;
+
+        let req = test::TestRequest::post()
+            .uri("/divide")
+            .set_json(&DivisionRequest {
+                names: vec!["髙橋一郎".to_string()],
+                mode: "basic".to_string(),
+                check_encoding: Some("jisx0208".to_string()),
+            })
+            .to_request();
+
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let encodability = &resp["divided_names"][0]["encodability"];
+        assert_eq!(encodability["target"], "jisx0208");
+        assert_eq!(encodability["family"][0]["code_point"], "U+9AD9");
+        assert_eq!(encodability["family"][0]["substitute"], "高");
+        assert_eq!(encodability["given"].as_array().unwrap().len(), 0);
+
+        let req = test::TestRequest::post()
+            .uri("/divide")
+            .set_json(&DivisionRequest {
+                names: vec!["髙橋一郎".to_string()],
+                mode: "basic".to_string(),
+                check_encoding: Some("utf-8".to_string()),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 422);
+    }
 }
@@ -0,0 +1,308 @@
+//! Throughput of the scoring hot path.
+//!
+//! Run with `cargo bench -p namedivider-rs`. The GBDT divider is left out since its time
+//! is dominated by LightGBM prediction rather than by feature extraction.
+//!
+//! The `split scoring` lines score every split of every name, once as the basic divider
+//! does and once with `string_slices`, a copy of the scoring path before it worked on
+//! `&str` views, which is kept as the baseline of PERFORMANCE_COMPARISON.md.
+//!
+//! `basic (compiled assets)` looks the kanji up by binary search over the records of a
+//! bundle written by `compile_assets`, as `--assets` does, instead of in a hash map.
+use namedivider_rs::divider::basic_name_divider::{
+    get_basic_name_divider_with_repository, BasicNameDivider,
+};
+use namedivider_rs::divider::basic_score_weights::BasicScoreWeights;
+use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::score_calculator::ScoreCalculator;
+use namedivider_rs::feature::assets::Asset;
+use namedivider_rs::feature::compiled_assets::{compile_assets, CompiledAssets};
+use namedivider_rs::feature::kanji::{parse_kanji_statistics, KanjiStatisticsRepository};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const NAMES: &[&str] = &[
+    "菅義偉",
+    "田中太郎",
+    "佐藤花子",
+    "山田一郎",
+    "鈴木二郎",
+    "高橋三郎",
+    "渡辺四郎",
+    "伊藤五郎",
+    "中村六郎",
+    "小林七郎",
+    "中曽根康弘",
+    "長谷川博己",
+    "勅使河原宏",
+    "武者小路実篤",
+    "西園寺公望",
+    "宇多田ヒカル",
+    "きゃりーぱみゅぱみゅ",
+    "髙橋一生",
+    "𠮷田沙保里",
+    "小泉進次郎",
+];
+
+/// The scoring path as it was before split candidates became `&str` views: every split
+/// is two new `String`s, kanji statistics are looked up by a `String` key in a SipHash
+/// map, and masks are `Vec`s built per char.
+mod string_slices {
+    use namedivider_rs::divider::basic_score_weights::BasicScoreWeights;
+    use namedivider_rs::feature::assets::Asset;
+    use namedivider_rs::feature::kanji::{parse_kanji_statistics, KanjiStatistics};
+    use std::collections::HashMap;
+
+    pub struct StringSliceScoreCalculator {
+        kanji_dict: HashMap<String, KanjiStatistics>,
+        default_kanji: KanjiStatistics,
+        weights: BasicScoreWeights,
+    }
+
+    fn slice(undivided_name: &str, idx: usize) -> (String, String) {
+        let family: String = undivided_name.chars().take(idx).collect();
+        let given: String = undivided_name.chars().skip(idx).collect();
+        (family, given)
+    }
+
+    fn create_order_mask(full_name_length: usize, char_idx: usize) -> Vec<i32> {
+        if full_name_length == 3 {
+            return vec![0, 0, 1, 1, 0, 0];
+        }
+        if char_idx == 1 {
+            return vec![0, 1, 1, 1, 0, 0];
+        }
+        if char_idx == full_name_length - 2 {
+            return vec![0, 0, 1, 1, 1, 0];
+        }
+        vec![0, 1, 1, 1, 1, 0]
+    }
+
+    fn create_length_mask(full_name_length: usize, char_idx: usize) -> Vec<i32> {
+        let min_family = char_idx + 1;
+        let max_family = full_name_length - 1;
+        let min_given = full_name_length - char_idx;
+        let max_given = full_name_length - 1;
+        let mut lc_family = vec![0, 0, 0, 0];
+        if min_family <= max_family {
+            for lc in &mut lc_family[min_family.min(4) - 1..max_family.min(4)] {
+                *lc = 1;
+            }
+        }
+        let mut lc_given = vec![0, 0, 0, 0];
+        if min_given <= max_given {
+            for lc in &mut lc_given[min_given.min(4) - 1..max_given.min(4)] {
+                *lc = 1;
+            }
+        }
+        lc_family.append(&mut lc_given);
+        lc_family
+    }
+
+    fn order_status(piece: &str, idx: usize, is_family: bool) -> usize {
+        if idx == 0 {
+            if is_family {
+                0
+            } else {
+                3
+            }
+        } else if idx == piece.chars().count() - 1 {
+            if is_family {
+                2
+            } else {
+                5
+            }
+        } else if is_family {
+            1
+        } else {
+            4
+        }
+    }
+
+    fn length_status(piece: &str, is_family: bool) -> usize {
+        let length = piece.chars().count().min(4);
+        if is_family {
+            length - 1
+        } else {
+            length - 1 + 4
+        }
+    }
+
+    fn masked_score(counts: &[i32], mask: &[i32], status: usize) -> Option<f64> {
+        let mut masked_counts = vec![0; mask.len()];
+        for ((masked_count, count), m) in masked_counts.iter_mut().zip(counts).zip(mask) {
+            *masked_count = count * m;
+        }
+        let sum = f64::from(masked_counts.iter().sum::<i32>());
+        if sum == 0.0 {
+            return None;
+        }
+        Some(f64::from(masked_counts[status]) / sum)
+    }
+
+    impl StringSliceScoreCalculator {
+        pub fn new() -> Self {
+            let contents = Asset::get("kanji.json").unwrap();
+            let contents = std::str::from_utf8(contents.data.as_ref()).unwrap();
+            let kanji_dict = parse_kanji_statistics(contents)
+                .unwrap()
+                .into_iter()
+                .map(|kanji_statistics| (kanji_statistics.kanji.clone(), kanji_statistics))
+                .collect();
+            Self {
+                kanji_dict,
+                default_kanji: KanjiStatistics {
+                    kanji: "default".to_string(),
+                    order_counts: vec![0; 6],
+                    length_counts: vec![0; 8],
+                },
+                weights: BasicScoreWeights::default(),
+            }
+        }
+
+        fn get(&self, kanji: &str) -> &KanjiStatistics {
+            self.kanji_dict.get(kanji).unwrap_or(&self.default_kanji)
+        }
+
+        fn order_score(&self, piece: &str, full_name_length: usize, start: usize) -> f64 {
+            let is_family = start == 0;
+            let mut scores = 0.0;
+            for (idx, kanji) in piece.chars().enumerate() {
+                let current_idx = start + idx;
+                if current_idx == 0 || current_idx == full_name_length - 1 {
+                    continue;
+                }
+                let mask = create_order_mask(full_name_length, current_idx);
+                let counts = &self.get(&kanji.to_string()).order_counts;
+                scores +=
+                    masked_score(counts, &mask, order_status(piece, idx, is_family)).unwrap_or(0.0);
+            }
+            scores
+        }
+
+        fn length_score(&self, piece: &str, full_name_length: usize, start: usize) -> f64 {
+            let is_family = start == 0;
+            let mut scores = 0.0;
+            for (idx, kanji) in piece.chars().enumerate() {
+                let mask = create_length_mask(full_name_length, start + idx);
+                let counts = &self.get(&kanji.to_string()).length_counts;
+                scores +=
+                    masked_score(counts, &mask, length_status(piece, is_family)).unwrap_or(0.0);
+            }
+            scores
+        }
+
+        fn calc_score(&self, family: &str, given: &str) -> f64 {
+            let fullname_length = family.chars().count() + given.chars().count();
+            let family_length = family.chars().count();
+            let order_score = (self.order_score(family, fullname_length, 0)
+                + self.order_score(given, fullname_length, family_length))
+                / ((fullname_length - 2) as f64);
+            let length_score = (self.length_score(family, fullname_length, 0)
+                + self.length_score(given, fullname_length, family_length))
+                / (fullname_length as f64);
+            if fullname_length == 4 {
+                return order_score;
+            }
+            self.weights
+                .combine(fullname_length, order_score, length_score)
+        }
+
+        /// Scores of every split, as `divide_by_algorithm` computed them.
+        pub fn score_splits(&self, undivided_name: &str) -> Vec<f64> {
+            (1..undivided_name.chars().count())
+                .map(|idx| {
+                    let (family, given) = slice(undivided_name, idx);
+                    self.calc_score(&family, &given)
+                })
+                .collect()
+        }
+    }
+}
+
+fn bench(label: &str, iterations: usize, mut f: impl FnMut()) {
+    // Warm up so that lazily initialized state does not count.
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let names = (iterations * NAMES.len()) as f64;
+    println!(
+        "{:<32} {:>10.0} names/s {:>8.3} us/name",
+        label,
+        names / elapsed.as_secs_f64(),
+        per_name_micros(elapsed, names)
+    );
+}
+
+fn per_name_micros(elapsed: Duration, names: f64) -> f64 {
+    elapsed.as_secs_f64() * 1_000_000.0 / names
+}
+
+fn main() {
+    let iterations = 20_000;
+    let names: Vec<String> = NAMES.iter().map(|name| name.to_string()).collect();
+
+    let divider = BasicNameDivider::new(" ".to_string(), false, true);
+    let score_calculator = &divider.basic_score_calculator;
+    let string_slice_score_calculator = string_slices::StringSliceScoreCalculator::new();
+    for name in &names {
+        let scores: Vec<f64> = name
+            .char_indices()
+            .skip(1)
+            .map(|(byte_idx, _)| {
+                let (family, given) = name.split_at(byte_idx);
+                score_calculator.calc_score(family, given)
+            })
+            .collect();
+        assert_eq!(scores, string_slice_score_calculator.score_splits(name));
+    }
+    bench("split scoring (String slices)", iterations, || {
+        for name in &names {
+            black_box(string_slice_score_calculator.score_splits(black_box(name)));
+        }
+    });
+    bench("split scoring (&str views)", iterations, || {
+        for name in &names {
+            let name = black_box(name);
+            for (byte_idx, _) in name.char_indices().skip(1) {
+                let (family, given) = name.split_at(byte_idx);
+                black_box(score_calculator.calc_score(family, given));
+            }
+        }
+    });
+
+    bench("basic", iterations, || {
+        for name in &names {
+            black_box(divider.divide_name(black_box(name)));
+        }
+    });
+
+    let divider = BasicNameDivider::new(" ".to_string(), true, true);
+    bench("basic (normalize_name)", iterations, || {
+        for name in &names {
+            black_box(divider.divide_name(black_box(name)));
+        }
+    });
+
+    let kanji_json = Asset::get("kanji.json").unwrap();
+    let kanji_statistics =
+        parse_kanji_statistics(std::str::from_utf8(&kanji_json.data).unwrap()).unwrap();
+    let bytes = compile_assets(&kanji_statistics, "", None, "").unwrap();
+    let compiled_assets = CompiledAssets::from_bytes(bytes).unwrap();
+    let divider = get_basic_name_divider_with_repository(
+        " ".to_string(),
+        false,
+        true,
+        BasicScoreWeights::default(),
+        Arc::new(KanjiStatisticsRepository::from_compiled(&compiled_assets)),
+    );
+    bench("basic (compiled assets)", iterations, || {
+        for name in &names {
+            black_box(divider.divide_name(black_box(name)));
+        }
+    });
+}
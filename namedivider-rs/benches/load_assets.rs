@@ -0,0 +1,75 @@
+//! Startup cost of the kanji statistics and family name repositories.
+//!
+//! Run with `cargo bench -p namedivider-rs --bench load_assets`. `json/txt` parses the
+//! embedded `kanji.json` and `family_names.txt` as the default dividers do; `compiled`
+//! maps a bundle written by `compile_assets` and serves lookups from its records, as
+//! `--assets` does. Both then look up every char of a name and its family name, so
+//! that the first lookups count as part of the startup.
+use namedivider_rs::feature::assets::Asset;
+use namedivider_rs::feature::compiled_assets::{
+    compile_assets, map_compiled_assets, CompiledAssets,
+};
+use namedivider_rs::feature::family_name::FamilyNameRepository;
+use namedivider_rs::feature::kanji::{parse_kanji_statistics, KanjiStatisticsRepository};
+use std::hint::black_box;
+use std::time::Instant;
+
+fn embedded_asset(file_name: &str) -> String {
+    String::from_utf8(Asset::get(file_name).unwrap().data.to_vec()).unwrap()
+}
+
+fn look_up(
+    kanji_statistics_repository: &KanjiStatisticsRepository,
+    family_name_repository: &FamilyNameRepository,
+) {
+    for kanji in "中曽根康弘".chars() {
+        black_box(kanji_statistics_repository.length_counts(kanji));
+    }
+    black_box(family_name_repository.get_rank("中曽根"));
+}
+
+fn main() {
+    let kanji_json = embedded_asset("kanji.json");
+    let family_names = embedded_asset("family_names.txt");
+    let bytes = compile_assets(
+        &parse_kanji_statistics(&kanji_json).unwrap(),
+        &family_names,
+        None,
+        "",
+    )
+    .unwrap();
+    let assets_path = std::env::temp_dir().join(format!("load_assets_{}.bin", std::process::id()));
+    std::fs::write(&assets_path, bytes).unwrap();
+
+    bench("json/txt", 20, || {
+        let kanji_statistics_repository =
+            KanjiStatisticsRepository::from_json(black_box(&kanji_json)).unwrap();
+        let family_name_repository = FamilyNameRepository::from_text(black_box(&family_names));
+        look_up(&kanji_statistics_repository, &family_name_repository);
+    });
+    bench("compiled", 2_000, || {
+        let compiled_assets =
+            CompiledAssets::from_bytes(map_compiled_assets(&assets_path).unwrap()).unwrap();
+        let kanji_statistics_repository =
+            KanjiStatisticsRepository::from_compiled(&compiled_assets);
+        let family_name_repository = FamilyNameRepository::from_compiled(&compiled_assets);
+        look_up(&kanji_statistics_repository, &family_name_repository);
+    });
+
+    std::fs::remove_file(&assets_path).unwrap();
+}
+
+fn bench(label: &str, iterations: usize, mut f: impl FnMut()) {
+    // Warm up so that the file is in the page cache for both.
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<32} {:>10.3} ms/load",
+        label,
+        elapsed.as_secs_f64() * 1_000.0 / iterations as f64
+    );
+}
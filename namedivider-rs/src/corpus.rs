@@ -0,0 +1,55 @@
+pub struct GoldName {
+    pub family: String,
+    pub given: String,
+}
+
+impl GoldName {
+    pub fn undivided_name(&self) -> String {
+        self.family.clone() + &self.given
+    }
+}
+
+/// Every split the algorithm would score for a gold name, with the index of the correct one.
+pub struct GoldSplitCandidates {
+    pub candidates: Vec<(String, String)>,
+    pub gold_idx: usize,
+}
+
+pub fn parse_divided_name(divided_name: &str, separator: &str) -> Option<GoldName> {
+    let (family, given) = divided_name.trim().split_once(separator)?;
+    if family.is_empty() || given.is_empty() {
+        return None;
+    }
+    Some(GoldName {
+        family: family.to_string(),
+        given: given.to_string(),
+    })
+}
+
+pub fn parse_divided_names(contents: &str, separator: &str) -> Vec<GoldName> {
+    contents
+        .lines()
+        .filter_map(|line| parse_divided_name(line, separator))
+        .collect()
+}
+
+/// Reads a gold name from the `family_column` and `given_column` (0-based) of a TSV line.
+pub fn parse_divided_name_columns(
+    divided_name: &str,
+    family_column: usize,
+    given_column: usize,
+) -> Option<GoldName> {
+    let fields: Vec<&str> = divided_name
+        .trim_end_matches(['\r', '\n'])
+        .split('\t')
+        .collect();
+    let family = fields.get(family_column)?.trim();
+    let given = fields.get(given_column)?.trim();
+    if family.is_empty() || given.is_empty() {
+        return None;
+    }
+    Some(GoldName {
+        family: family.to_string(),
+        given: given.to_string(),
+    })
+}
@@ -1,10 +1,18 @@
 pub mod basic_name_divider;
 pub mod basic_score_calculator;
+pub mod basic_score_weights;
 pub mod divided_name;
 pub mod gbdt_name_divider;
 pub mod gbdt_score_calculator;
+pub mod gbdt_trainer;
+pub mod logistic_name_divider;
+pub mod logistic_score_calculator;
+pub mod logistic_trainer;
 pub mod name_divider;
 pub mod name_divider_base;
+pub mod ngram_name_divider;
+pub mod ngram_score_calculator;
+pub mod provenance;
 pub mod score_calculator;
 pub mod two_char_name_divider;
 pub mod two_char_score_calculator;
@@ -1,42 +1,110 @@
 use crate::divider::basic_score_calculator::BasicScoreCalculator;
+use crate::divider::basic_score_weights::BasicScoreWeights;
 use crate::divider::divided_name::DividedName;
 use crate::divider::name_divider::NameDivider;
 use crate::divider::name_divider_base::NameDividerBase;
+use crate::divider::provenance::Provenance;
 use crate::feature::extractor::SimpleFeatureExtractor;
 use crate::feature::kanji::KanjiStatisticsRepository;
+use crate::feature::shared_assets;
 use regex::Regex;
+use std::sync::Arc;
 
 pub struct BasicNameDivider {
     pub basic_score_calculator: BasicScoreCalculator,
     pub name_divider_base: NameDividerBase,
 }
 
+impl BasicNameDivider {
+    pub fn new(separator: String, normalize_name: bool, only_order_score_when_4: bool) -> Self {
+        get_basic_name_divider_with_weights(
+            separator,
+            normalize_name,
+            only_order_score_when_4,
+            BasicScoreWeights::default(),
+        )
+    }
+}
+
 impl NameDivider for BasicNameDivider {
     fn divide_name(&self, undivided_name: &String) -> DividedName {
         self.name_divider_base
             .divide_name(undivided_name, &self.basic_score_calculator)
     }
+
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str> {
+        self.name_divider_base
+            .try_divide_name(undivided_name, &self.basic_score_calculator)
+    }
+
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        self.name_divider_base
+            .divide_name_candidates(undivided_name, &self.basic_score_calculator)
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.name_divider_base
+            .try_divide_name_candidates(undivided_name, &self.basic_score_calculator)
+    }
 }
 
+#[deprecated(
+    note = "use `BasicNameDivider::new`; the algorithm is told by `DividedName.provenance`"
+)]
 pub fn get_basic_name_divider(
     separator: String,
     normalize_name: bool,
     algorithm_name: String,
     only_order_score_when_4: bool,
 ) -> BasicNameDivider {
-    let repo = KanjiStatisticsRepository::new();
+    let mut divider = BasicNameDivider::new(separator, normalize_name, only_order_score_when_4);
+    divider.name_divider_base.provenance =
+        Provenance::with_algorithm_name(Provenance::Basic, algorithm_name);
+    divider
+}
+
+pub fn get_basic_name_divider_with_weights(
+    separator: String,
+    normalize_name: bool,
+    only_order_score_when_4: bool,
+    weights: BasicScoreWeights,
+) -> BasicNameDivider {
+    get_basic_name_divider_with_repository(
+        separator,
+        normalize_name,
+        only_order_score_when_4,
+        weights,
+        shared_assets::kanji_statistics_repository(),
+    )
+}
+
+/// Builds the divider on kanji statistics loaded elsewhere, e.g. by
+/// `KanjiStatisticsRepository::from_compiled`.
+pub fn get_basic_name_divider_with_repository(
+    separator: String,
+    normalize_name: bool,
+    only_order_score_when_4: bool,
+    weights: BasicScoreWeights,
+    repo: Arc<KanjiStatisticsRepository>,
+) -> BasicNameDivider {
     let feature_extractor = SimpleFeatureExtractor {
         kanji_statistics_repository: repo,
     };
     let basic_score_calculator = BasicScoreCalculator {
         feature_extractor,
         only_order_score_when_4,
+        weights,
     };
     let name_divider_base = NameDividerBase {
         separator,
         normalize_name,
-        algorithm_name,
+        provenance: Provenance::Basic,
         compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+        gaiji_mapping: None,
+        user_dictionary: None,
     };
     BasicNameDivider {
         basic_score_calculator,
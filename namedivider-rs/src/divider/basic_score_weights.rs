@@ -0,0 +1,199 @@
+use crate::corpus::GoldName;
+use crate::divider::basic_name_divider::BasicNameDivider;
+use serde::{Deserialize, Serialize};
+
+const WEIGHT_GRID_SIZE: usize = 20;
+
+/// How much to trust the order score over the length score, per full name length.
+/// Each value is the weight of the order score; the length score gets the rest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BasicScoreWeights {
+    pub order_weight_3: f64,
+    pub order_weight_4: f64,
+    pub order_weight_5: f64,
+    pub order_weight_6_or_more: f64,
+}
+
+impl Default for BasicScoreWeights {
+    fn default() -> Self {
+        Self {
+            order_weight_3: 0.5,
+            order_weight_4: 0.5,
+            order_weight_5: 0.5,
+            order_weight_6_or_more: 0.5,
+        }
+    }
+}
+
+impl BasicScoreWeights {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn order_weight(&self, fullname_length: usize) -> f64 {
+        match fullname_length {
+            0..=3 => self.order_weight_3,
+            4 => self.order_weight_4,
+            5 => self.order_weight_5,
+            _ => self.order_weight_6_or_more,
+        }
+    }
+
+    fn order_weight_mut(&mut self, fullname_length: usize) -> &mut f64 {
+        match fullname_length {
+            0..=3 => &mut self.order_weight_3,
+            4 => &mut self.order_weight_4,
+            5 => &mut self.order_weight_5,
+            _ => &mut self.order_weight_6_or_more,
+        }
+    }
+
+    pub fn combine(&self, fullname_length: usize, order_score: f64, length_score: f64) -> f64 {
+        let order_weight = self.order_weight(fullname_length);
+        order_score * order_weight + length_score * (1.0 - order_weight)
+    }
+}
+
+pub struct LengthBucketFit {
+    pub label: &'static str,
+    pub num_names: usize,
+    pub correct_before: usize,
+    pub correct_after: usize,
+    pub order_weight: f64,
+}
+
+pub struct BasicScoreWeightsFit {
+    pub weights: BasicScoreWeights,
+    pub buckets: Vec<LengthBucketFit>,
+}
+
+/// Order and length scores of every split candidate of one gold name.
+struct ScoredCandidates {
+    fullname_length: usize,
+    gold_idx: usize,
+    scores: Vec<(f64, f64)>,
+}
+
+impl ScoredCandidates {
+    fn is_correct(&self, weights: &BasicScoreWeights) -> bool {
+        let mut max_idx = 0;
+        let mut max_val = f64::NEG_INFINITY;
+        for (i, (order_score, length_score)) in self.scores.iter().enumerate() {
+            let score = weights.combine(self.fullname_length, *order_score, *length_score);
+            if max_val < score {
+                max_val = score;
+                max_idx = i;
+            }
+        }
+        max_idx == self.gold_idx
+    }
+}
+
+fn bucket_index(fullname_length: usize) -> usize {
+    match fullname_length {
+        0..=3 => 0,
+        4 => 1,
+        5 => 2,
+        _ => 3,
+    }
+}
+
+/// Fits the order weight of each length bucket by grid search over a gold corpus.
+///
+/// Names that the rule base divides are skipped since the weights never affect them.
+/// On ties the weight closest to the current one is kept.
+pub fn fit_basic_score_weights(
+    gold_names: &[GoldName],
+    divider: &BasicNameDivider,
+) -> BasicScoreWeightsFit {
+    let calculator = &divider.basic_score_calculator;
+    let name_divider_base = &divider.name_divider_base;
+
+    let mut buckets: Vec<Vec<ScoredCandidates>> =
+        vec![Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+    for gold_name in gold_names {
+        let gold_split_candidates = match name_divider_base.gold_split_candidates(gold_name) {
+            Some(gold_split_candidates) => gold_split_candidates,
+            None => continue,
+        };
+        let fullname_length = gold_split_candidates.candidates.len() + 1;
+        let scores = gold_split_candidates
+            .candidates
+            .iter()
+            .map(|(family, given)| calculator.calc_order_and_length_scores(family, given))
+            .collect();
+        buckets[bucket_index(fullname_length)].push(ScoredCandidates {
+            fullname_length,
+            gold_idx: gold_split_candidates.gold_idx,
+            scores,
+        });
+    }
+
+    let labels = ["3", "4", "5", "6+"];
+    let representative_lengths = [3, 4, 5, 6];
+    let mut weights = calculator.weights.clone();
+    let mut bucket_fits = Vec::new();
+    for (i, candidates) in buckets.iter().enumerate() {
+        let fullname_length = representative_lengths[i];
+        let count_correct = |weights: &BasicScoreWeights| {
+            candidates.iter().filter(|c| c.is_correct(weights)).count()
+        };
+        let correct_before = count_correct(&weights);
+        let current_weight = weights.order_weight(fullname_length);
+        let mut best_weight = current_weight;
+        let mut best_correct = correct_before;
+        for step in 0..=WEIGHT_GRID_SIZE {
+            let order_weight = step as f64 / WEIGHT_GRID_SIZE as f64;
+            *weights.order_weight_mut(fullname_length) = order_weight;
+            let correct = count_correct(&weights);
+            let is_closer =
+                (order_weight - current_weight).abs() < (best_weight - current_weight).abs();
+            if correct > best_correct || (correct == best_correct && is_closer) {
+                best_weight = order_weight;
+                best_correct = correct;
+            }
+        }
+        *weights.order_weight_mut(fullname_length) = best_weight;
+        bucket_fits.push(LengthBucketFit {
+            label: labels[i],
+            num_names: candidates.len(),
+            correct_before,
+            correct_after: best_correct,
+            order_weight: best_weight,
+        });
+    }
+
+    BasicScoreWeightsFit {
+        weights,
+        buckets: bucket_fits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::divider::basic_score_weights::BasicScoreWeights;
+
+    #[test]
+    fn test_default_weights_average_scores() {
+        let weights = BasicScoreWeights::default();
+        assert_eq!(weights.combine(3, 0.2, 0.6), (0.2 + 0.6) / 2.0);
+        assert_eq!(weights.combine(7, 0.9, 0.3), (0.9 + 0.3) / 2.0);
+    }
+
+    #[test]
+    fn test_weights_json_round_trip() {
+        let weights = BasicScoreWeights {
+            order_weight_3: 0.25,
+            order_weight_4: 1.0,
+            order_weight_5: 0.6,
+            order_weight_6_or_more: 0.45,
+        };
+        let restored = BasicScoreWeights::from_json(&weights.to_json()).unwrap();
+        assert_eq!(restored, weights);
+        assert_eq!(restored.order_weight(8), 0.45);
+    }
+}
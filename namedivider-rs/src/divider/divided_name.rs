@@ -1,7 +1,37 @@
+use crate::divider::provenance::Provenance;
+use serde::Serialize;
+
+/// Half-open range of a part of the name in the undivided input, in chars and in UTF-8 bytes.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub char_start: usize,
+    pub char_end: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
 pub struct DividedName {
+    /// Family name as written in the input.
     pub family: String,
+    /// Given name as written in the input.
     pub given: String,
     pub separator: String,
     pub score: f64,
+    pub provenance: Provenance,
+    /// Family name after normalization, as it was scored.
+    pub normalized_family: String,
+    /// Given name after normalization, as it was scored.
+    pub normalized_given: String,
+    pub family_span: Span,
+    pub given_span: Span,
+    /// Same as `algorithm()`, kept for code written before `provenance`.
+    #[deprecated(note = "use `provenance` or `algorithm()`")]
     pub algorithm: String,
 }
+
+impl DividedName {
+    /// Backward-compatible algorithm string, such as "rule", "kanji_feature" or "gbdt".
+    pub fn algorithm(&self) -> String {
+        self.provenance.algorithm_name()
+    }
+}
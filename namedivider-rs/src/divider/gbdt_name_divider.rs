@@ -2,53 +2,169 @@ use crate::divider::divided_name::DividedName;
 use crate::divider::gbdt_score_calculator::GBDTScoreCalculator;
 use crate::divider::name_divider::NameDivider;
 use crate::divider::name_divider_base::NameDividerBase;
+use crate::divider::provenance::{Provenance, EMBEDDED_GBDT_MODEL_VERSION};
 use crate::feature::assets::Asset;
+use crate::feature::compiled_assets::CompiledAssets;
 use crate::feature::extractor::FamilyRankingFeatureExtractor;
 use crate::feature::family_name::FamilyNameRepository;
 use crate::feature::kanji::KanjiStatisticsRepository;
 use lightgbm::Booster;
 use regex::Regex;
-use std::sync::Arc;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
 
 pub struct GBDTNameDivider {
     pub gbdt_score_calculator: GBDTScoreCalculator,
     pub name_divider_base: NameDividerBase,
 }
 
+impl GBDTNameDivider {
+    /// Uses the embedded model and assets.
+    pub fn new(separator: String, normalize_name: bool) -> Self {
+        get_gbdt_name_divider_with_extractor(
+            separator,
+            normalize_name,
+            FamilyRankingFeatureExtractor::shared(),
+        )
+    }
+}
+
 impl NameDivider for GBDTNameDivider {
     fn divide_name(&self, undivided_name: &String) -> DividedName {
         self.name_divider_base
             .divide_name(undivided_name, &self.gbdt_score_calculator)
     }
+
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str> {
+        self.name_divider_base
+            .try_divide_name(undivided_name, &self.gbdt_score_calculator)
+    }
+
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        self.name_divider_base
+            .divide_name_candidates(undivided_name, &self.gbdt_score_calculator)
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.name_divider_base
+            .try_divide_name_candidates(undivided_name, &self.gbdt_score_calculator)
+    }
+}
+
+/// The embedded model text and its hash, loaded on first use and shared by every divider.
+fn embedded_gbdt_model() -> &'static (Arc<String>, u64) {
+    static MODEL: OnceLock<(Arc<String>, u64)> = OnceLock::new();
+    MODEL.get_or_init(|| {
+        let contents = Asset::get("gbdt_model_v1.txt")
+            .unwrap()
+            .data
+            .as_ref()
+            .to_owned();
+        let model_str = std::str::from_utf8(&contents).unwrap();
+        let model_string = Arc::new(model_str.to_string());
+        let model_hash = hash_model(&model_string);
+        (model_string, model_hash)
+    })
 }
 
+fn hash_model(model_string: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    model_string.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[deprecated(
+    note = "use `GBDTNameDivider::new`; the algorithm is told by `DividedName.provenance`"
+)]
 pub fn get_gbdt_name_divider(
     separator: String,
     normalize_name: bool,
     algorithm_name: String,
 ) -> GBDTNameDivider {
-    let kanji_statistics_repository = KanjiStatisticsRepository::new();
-    let family_name_repository = FamilyNameRepository::new();
+    let mut divider = GBDTNameDivider::new(separator, normalize_name);
+    divider.name_divider_base.provenance = Provenance::with_algorithm_name(
+        divider.name_divider_base.provenance.clone(),
+        algorithm_name,
+    );
+    divider
+}
+
+/// Builds the divider with the embedded model on repositories loaded elsewhere.
+pub fn get_gbdt_name_divider_with_extractor(
+    separator: String,
+    normalize_name: bool,
+    feature_extractor: FamilyRankingFeatureExtractor,
+) -> GBDTNameDivider {
+    let (model_string, model_hash) = embedded_gbdt_model();
+    build_gbdt_name_divider(
+        separator,
+        normalize_name,
+        feature_extractor,
+        model_string.clone(),
+        *model_hash,
+        EMBEDDED_GBDT_MODEL_VERSION,
+    )
+}
+
+/// Builds the divider with a LightGBM model other than the embedded one, such as one
+/// written by `train-gbdt`, on the shared embedded repositories.
+pub fn get_gbdt_name_divider_with_model(
+    separator: String,
+    normalize_name: bool,
+    model_string: String,
+    model_version: &str,
+) -> Result<GBDTNameDivider, &'static str> {
+    Booster::from_string(&model_string).map_err(|_| "Invalid LightGBM model.")?;
+    let model_hash = hash_model(&model_string);
+    Ok(build_gbdt_name_divider(
+        separator,
+        normalize_name,
+        FamilyRankingFeatureExtractor::shared(),
+        Arc::new(model_string),
+        model_hash,
+        model_version,
+    ))
+}
+
+/// Builds the divider from a bundle written by `compile-assets` instead of the embedded assets.
+pub fn get_gbdt_name_divider_from_compiled(
+    separator: String,
+    normalize_name: bool,
+    compiled_assets: &CompiledAssets,
+) -> Result<GBDTNameDivider, &'static str> {
+    let model_str = compiled_assets
+        .gbdt_model()
+        .ok_or("Compiled assets have no GBDT model.")?;
     let feature_extractor = FamilyRankingFeatureExtractor {
-        kanji_statistics_repository,
-        family_name_repository,
+        kanji_statistics_repository: Arc::new(KanjiStatisticsRepository::from_compiled(
+            compiled_assets,
+        )),
+        family_name_repository: Arc::new(FamilyNameRepository::from_compiled(compiled_assets)),
     };
-
-    let contents = Asset::get("gbdt_model_v1.txt")
-        .unwrap()
-        .data
-        .as_ref()
-        .to_owned();
-    let model_str = std::str::from_utf8(&contents).unwrap();
     let model_string = Arc::new(model_str.to_string());
+    let model_hash = hash_model(&model_string);
+    Ok(build_gbdt_name_divider(
+        separator,
+        normalize_name,
+        feature_extractor,
+        model_string,
+        model_hash,
+        compiled_assets.gbdt_model_version(),
+    ))
+}
 
-    // Pre-compute hash once during initialization for fast lookup
-    let mut hasher = DefaultHasher::new();
-    model_string.hash(&mut hasher);
-    let model_hash = hasher.finish();
-
+fn build_gbdt_name_divider(
+    separator: String,
+    normalize_name: bool,
+    feature_extractor: FamilyRankingFeatureExtractor,
+    model_string: Arc<String>,
+    model_hash: u64,
+    model_version: &str,
+) -> GBDTNameDivider {
     let gbdt_score_calculator = GBDTScoreCalculator {
         feature_extractor,
         model_string,
@@ -57,8 +173,12 @@ pub fn get_gbdt_name_divider(
     let name_divider_base = NameDividerBase {
         separator,
         normalize_name,
-        algorithm_name,
+        provenance: Provenance::Gbdt {
+            model_version: model_version.to_string(),
+        },
         compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+        gaiji_mapping: None,
+        user_dictionary: None,
     };
     GBDTNameDivider {
         gbdt_score_calculator,
@@ -17,7 +17,7 @@ pub struct GBDTScoreCalculator {
 }
 
 impl ScoreCalculator for GBDTScoreCalculator {
-    fn calc_score(&self, family: &String, given: &String) -> f64 {
+    fn calc_score(&self, family: &str, given: &str) -> f64 {
         let features = self.feature_extractor.get_features(family, given);
         
         // Maximum efficiency: single model per thread with zero-cost lookup
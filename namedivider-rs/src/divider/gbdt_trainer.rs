@@ -0,0 +1,139 @@
+use crate::corpus::GoldName;
+use crate::divider::name_divider_base::NameDividerBase;
+use crate::feature::extractor::FamilyRankingFeatureExtractor;
+use lightgbm::{Booster, Dataset};
+use serde_json::{json, Map, Value};
+
+/// The features of one split candidate of a gold name, as the GBDT divider scores it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureRow {
+    /// Index of the gold name among those that produced rows, shared by its candidates.
+    pub group_id: usize,
+    pub family: String,
+    pub given: String,
+    /// Whether this candidate is the gold split.
+    pub is_gold: bool,
+    /// `FamilyRankingFeatures::to_vec`, named by `FAMILY_RANKING_FEATURE_NAMES`.
+    pub features: Vec<f64>,
+}
+
+/// Turns every split candidate of every gold name into a `FeatureRow`, grouped by name.
+///
+/// Names that the rule base divides are skipped, as the GBDT model never scores them,
+/// and do not take a group id.
+pub fn family_ranking_feature_rows(
+    gold_names: &[GoldName],
+    feature_extractor: &FamilyRankingFeatureExtractor,
+    name_divider_base: &NameDividerBase,
+) -> Vec<FeatureRow> {
+    let mut rows = Vec::new();
+    let mut group_id = 0;
+    for gold_name in gold_names {
+        let gold_split_candidates = match name_divider_base.gold_split_candidates(gold_name) {
+            Some(gold_split_candidates) => gold_split_candidates,
+            None => continue,
+        };
+        for (i, (family, given)) in gold_split_candidates.candidates.into_iter().enumerate() {
+            let features = feature_extractor.get_features(&family, &given).to_vec();
+            rows.push(FeatureRow {
+                group_id,
+                family,
+                given,
+                is_gold: i == gold_split_candidates.gold_idx,
+                features,
+            });
+        }
+        group_id += 1;
+    }
+    rows
+}
+
+pub struct GBDTTrainerConfig {
+    pub num_iterations: usize,
+    pub learning_rate: f64,
+    pub num_leaves: usize,
+    pub min_data_in_leaf: usize,
+    /// Further LightGBM parameters, overriding the ones above.
+    pub extra_params: Map<String, Value>,
+}
+
+impl Default for GBDTTrainerConfig {
+    /// The parameters of the embedded model, with fewer iterations as there is no
+    /// early stopping.
+    fn default() -> Self {
+        Self {
+            num_iterations: 300,
+            learning_rate: 0.1,
+            num_leaves: 31,
+            min_data_in_leaf: 20,
+            extra_params: Map::new(),
+        }
+    }
+}
+
+impl GBDTTrainerConfig {
+    pub fn to_params(&self) -> Value {
+        let mut params = json!({
+            "objective": "binary",
+            "num_iterations": self.num_iterations,
+            "learning_rate": self.learning_rate,
+            "num_leaves": self.num_leaves,
+            "min_data_in_leaf": self.min_data_in_leaf,
+            "verbose": -1,
+        });
+        for (key, value) in &self.extra_params {
+            params[key] = value.clone();
+        }
+        params
+    }
+}
+
+/// Trains a binary LightGBM model that scores the gold split of each name above the
+/// other candidates, on the features `GBDTNameDivider` computes.
+///
+/// Every split candidate is one sample, as in `family_ranking_feature_rows`. The model
+/// can be loaded with `get_gbdt_name_divider_with_model`.
+pub fn train_gbdt_model(
+    gold_names: &[GoldName],
+    feature_extractor: &FamilyRankingFeatureExtractor,
+    name_divider_base: &NameDividerBase,
+    config: &GBDTTrainerConfig,
+) -> lightgbm::Result<Booster> {
+    let rows = family_ranking_feature_rows(gold_names, feature_extractor, name_divider_base);
+    let labels = rows.iter().map(|row| row.is_gold as i32 as f32).collect();
+    let features = rows.into_iter().map(|row| row.features).collect();
+    let dataset = Dataset::from_mat(features, labels)?;
+    Booster::train(dataset, &config.to_params())
+}
+
+/// Splits gold names into training and validation names, putting about
+/// `validation_ratio` of them in validation.
+///
+/// A name goes to the same side for the same `seed` whatever else is in the corpus,
+/// so a duplicated name never ends up on both sides.
+pub fn split_validation_names(
+    gold_names: Vec<GoldName>,
+    validation_ratio: f64,
+    seed: u64,
+) -> (Vec<GoldName>, Vec<GoldName>) {
+    let threshold = (validation_ratio.clamp(0.0, 1.0) * u32::MAX as f64) as u64;
+    gold_names.into_iter().partition(|gold_name| {
+        // FNV-1a with the MurmurHash3 finalizer, which unlike `DefaultHasher` is the same
+        // on every platform and version.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+        for byte in gold_name
+            .family
+            .bytes()
+            .chain([b' '])
+            .chain(gold_name.given.bytes())
+        {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        hash ^= hash >> 33;
+        (hash >> 32) >= threshold
+    })
+}
@@ -0,0 +1,64 @@
+use crate::divider::divided_name::DividedName;
+use crate::divider::logistic_score_calculator::{LogisticModel, LogisticScoreCalculator};
+use crate::divider::name_divider::NameDivider;
+use crate::divider::name_divider_base::NameDividerBase;
+use crate::divider::provenance::Provenance;
+use crate::feature::extractor::FamilyRankingFeatureExtractor;
+use regex::Regex;
+
+pub struct LogisticNameDivider {
+    pub logistic_score_calculator: LogisticScoreCalculator,
+    pub name_divider_base: NameDividerBase,
+}
+
+impl NameDivider for LogisticNameDivider {
+    fn divide_name(&self, undivided_name: &String) -> DividedName {
+        self.name_divider_base
+            .divide_name(undivided_name, &self.logistic_score_calculator)
+    }
+
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str> {
+        self.name_divider_base
+            .try_divide_name(undivided_name, &self.logistic_score_calculator)
+    }
+
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        self.name_divider_base
+            .divide_name_candidates(undivided_name, &self.logistic_score_calculator)
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.name_divider_base
+            .try_divide_name_candidates(undivided_name, &self.logistic_score_calculator)
+    }
+}
+
+pub fn get_logistic_name_divider(
+    separator: String,
+    normalize_name: bool,
+    model: LogisticModel,
+) -> LogisticNameDivider {
+    let feature_extractor = FamilyRankingFeatureExtractor::shared();
+    let logistic_score_calculator = LogisticScoreCalculator {
+        feature_extractor,
+        model,
+    };
+    let name_divider_base = NameDividerBase {
+        separator,
+        normalize_name,
+        provenance: Provenance::Model {
+            name: "logistic".to_string(),
+            version: None,
+        },
+        compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+        gaiji_mapping: None,
+        user_dictionary: None,
+    };
+    LogisticNameDivider {
+        logistic_score_calculator,
+        name_divider_base,
+    }
+}
@@ -0,0 +1,119 @@
+use crate::divider::score_calculator::ScoreCalculator;
+use crate::feature::extractor::{
+    FamilyRankingFeatureExtractor, FamilyRankingFeatures, FAMILY_RANKING_FEATURE_NAMES,
+};
+use serde::de::Error;
+use serde::{Deserialize, Serialize};
+
+pub const BASE_FEATURE_NAMES: [&str; 9] = FAMILY_RANKING_FEATURE_NAMES;
+
+pub const EXTRA_FEATURE_NAMES: [&str; 4] = [
+    "rank_missing",
+    "log_rank",
+    "order_score_mean",
+    "length_score_mean",
+];
+
+/// Turns `FamilyRankingFeatures` into the input vector of the linear model.
+///
+/// A missing rank is NaN in `FamilyRankingFeatures`, which a linear model cannot take,
+/// so it is replaced by `missing_rank`, which should be beyond the last rank so that an
+/// unknown family name looks rarer than any known one. It is also flagged by
+/// `rank_missing` when extra features are used.
+pub fn to_logistic_features(
+    features: &FamilyRankingFeatures,
+    missing_rank: f64,
+    use_extra_features: bool,
+) -> Vec<f64> {
+    let rank_missing = features.rank.is_nan();
+    let rank = if rank_missing {
+        missing_rank
+    } else {
+        features.rank
+    };
+    let mut values = features.to_vec();
+    values[0] = rank;
+    if use_extra_features {
+        let log_rank = rank.ln_1p();
+        let order_score_mean = (features.family_order_score + features.given_order_score)
+            / (features.fullname_length - 2.0).max(1.0);
+        let length_score_mean =
+            (features.family_length_score + features.given_length_score) / features.fullname_length;
+        values.push(rank_missing as i32 as f64);
+        values.push(log_rank);
+        values.push(order_score_mean);
+        values.push(length_score_mean);
+    }
+    values
+}
+
+pub fn logistic_feature_names(use_extra_features: bool) -> Vec<String> {
+    let mut names: Vec<String> = BASE_FEATURE_NAMES.iter().map(|s| s.to_string()).collect();
+    if use_extra_features {
+        names.extend(EXTRA_FEATURE_NAMES.iter().map(|s| s.to_string()));
+    }
+    names
+}
+
+/// Standardized logistic regression weights, serialized as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogisticModel {
+    pub feature_names: Vec<String>,
+    pub use_extra_features: bool,
+    /// The rank given to an unknown family name, one past the last rank of the family
+    /// names the model was trained with.
+    pub missing_rank: f64,
+    pub means: Vec<f64>,
+    pub scales: Vec<f64>,
+    pub weights: Vec<f64>,
+    pub bias: f64,
+}
+
+impl LogisticModel {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let model: LogisticModel = serde_json::from_str(json)?;
+        if model.feature_names != logistic_feature_names(model.use_extra_features) {
+            return Err(serde_json::Error::custom(
+                "feature_names do not match the features of this version",
+            ));
+        }
+        let num_features = model.feature_names.len();
+        if model.means.len() != num_features
+            || model.scales.len() != num_features
+            || model.weights.len() != num_features
+        {
+            return Err(serde_json::Error::custom(
+                "means, scales and weights must have one value per feature",
+            ));
+        }
+        Ok(model)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn predict(&self, values: &[f64]) -> f64 {
+        let mut z = self.bias;
+        for (i, value) in values.iter().enumerate() {
+            z += self.weights[i] * (value - self.means[i]) / self.scales[i];
+        }
+        1.0 / (1.0 + (-z).exp())
+    }
+}
+
+pub struct LogisticScoreCalculator {
+    pub feature_extractor: FamilyRankingFeatureExtractor,
+    pub model: LogisticModel,
+}
+
+impl ScoreCalculator for LogisticScoreCalculator {
+    fn calc_score(&self, family: &str, given: &str) -> f64 {
+        let features = self.feature_extractor.get_features(family, given);
+        self.model.predict(&to_logistic_features(
+            &features,
+            self.model.missing_rank,
+            self.model.use_extra_features,
+        ))
+    }
+}
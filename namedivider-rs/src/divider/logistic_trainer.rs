@@ -0,0 +1,109 @@
+use crate::corpus::GoldName;
+use crate::divider::logistic_score_calculator::{
+    logistic_feature_names, to_logistic_features, LogisticModel,
+};
+use crate::divider::name_divider_base::NameDividerBase;
+use crate::feature::extractor::FamilyRankingFeatureExtractor;
+
+pub struct LogisticTrainerConfig {
+    pub epochs: usize,
+    pub learning_rate: f64,
+    pub l2: f64,
+    pub use_extra_features: bool,
+}
+
+impl Default for LogisticTrainerConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 300,
+            learning_rate: 0.5,
+            l2: 0.0001,
+            use_extra_features: true,
+        }
+    }
+}
+
+/// Fits a logistic regression that tells the correct split of each gold name from the others.
+///
+/// Every split candidate is one sample, labelled 1 for the gold split and 0 otherwise.
+/// Names that the rule base divides are skipped. Features are standardized before
+/// full-batch gradient descent, and the mean and scale are stored in the model.
+pub fn train_logistic_model(
+    gold_names: &[GoldName],
+    feature_extractor: &FamilyRankingFeatureExtractor,
+    name_divider_base: &NameDividerBase,
+    config: &LogisticTrainerConfig,
+) -> LogisticModel {
+    let mut samples: Vec<Vec<f64>> = Vec::new();
+    let mut labels: Vec<f64> = Vec::new();
+    let missing_rank = feature_extractor.family_name_repository.len() as f64;
+    for gold_name in gold_names {
+        let gold_split_candidates = match name_divider_base.gold_split_candidates(gold_name) {
+            Some(gold_split_candidates) => gold_split_candidates,
+            None => continue,
+        };
+        for (i, (family, given)) in gold_split_candidates.candidates.iter().enumerate() {
+            let features = feature_extractor.get_features(family, given);
+            samples.push(to_logistic_features(
+                &features,
+                missing_rank,
+                config.use_extra_features,
+            ));
+            labels.push((i == gold_split_candidates.gold_idx) as i32 as f64);
+        }
+    }
+
+    let feature_names = logistic_feature_names(config.use_extra_features);
+    let num_features = feature_names.len();
+    let num_samples = samples.len().max(1) as f64;
+
+    let mut means = vec![0.0; num_features];
+    for sample in &samples {
+        for (mean, value) in means.iter_mut().zip(sample) {
+            *mean += value / num_samples;
+        }
+    }
+    let mut scales = vec![0.0; num_features];
+    for sample in &samples {
+        for ((scale, value), mean) in scales.iter_mut().zip(sample).zip(&means) {
+            *scale += (value - mean).powi(2) / num_samples;
+        }
+    }
+    for scale in scales.iter_mut() {
+        *scale = if *scale > 0.0 { scale.sqrt() } else { 1.0 };
+    }
+    for sample in samples.iter_mut() {
+        for ((value, mean), scale) in sample.iter_mut().zip(&means).zip(&scales) {
+            *value = (*value - mean) / scale;
+        }
+    }
+
+    let mut weights = vec![0.0; num_features];
+    let mut bias = 0.0;
+    for _ in 0..config.epochs {
+        let mut weight_gradients = vec![0.0; num_features];
+        let mut bias_gradient = 0.0;
+        for (sample, label) in samples.iter().zip(&labels) {
+            let z: f64 = bias + sample.iter().zip(&weights).map(|(x, w)| x * w).sum::<f64>();
+            let error = 1.0 / (1.0 + (-z).exp()) - label;
+            for (gradient, x) in weight_gradients.iter_mut().zip(sample) {
+                *gradient += error * x / num_samples;
+            }
+            bias_gradient += error / num_samples;
+        }
+        for (weight, gradient) in weights.iter_mut().zip(&weight_gradients) {
+            *weight -= config.learning_rate * (gradient + config.l2 * *weight);
+        }
+        bias -= config.learning_rate * bias_gradient;
+    }
+
+    LogisticModel {
+        feature_names,
+        use_extra_features: config.use_extra_features,
+        missing_rank,
+        means,
+        scales,
+        weights,
+        bias,
+    }
+}
@@ -1,5 +1,23 @@
 use crate::divider::divided_name::DividedName;
 
 pub trait NameDivider {
+    /// Panics on a name of fewer than 2 chars; see `try_divide_name`.
     fn divide_name(&self, undivided_name: &String) -> DividedName;
+
+    /// Like `divide_name`, but a name that can't be divided is an error instead of a panic.
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str>;
+
+    /// Every way to divide the name, most probable first, each scored with its share
+    /// of the probability. A name divided by a rule has just that one candidate.
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        vec![self.divide_name(undivided_name)]
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.try_divide_name(undivided_name)
+            .map(|divided_name| vec![divided_name])
+    }
 }
@@ -1,46 +1,124 @@
-use crate::divider::divided_name::DividedName;
+use crate::corpus::{GoldName, GoldSplitCandidates};
+use crate::divider::divided_name::{DividedName, Span};
+use crate::divider::provenance::{Provenance, RuleId};
 use crate::divider::score_calculator::ScoreCalculator;
+use crate::feature::gaiji::GaijiMapping;
+use crate::feature::user_dictionary::UserDictionary;
 use regex::Regex;
+use std::cmp::Ordering;
+use std::sync::Arc;
 
-fn slice(undivided_name: &String, idx: usize) -> (String, String) {
-    let mut family = "".to_string();
-    let mut given = "".to_string();
-    for (i, c) in undivided_name.chars().enumerate() {
-        if i < idx {
-            family.push(c);
-        } else {
-            given.push(c);
-        }
+fn slice(undivided_name: &str, idx: usize) -> (&str, &str) {
+    let byte_idx = undivided_name
+        .char_indices()
+        .nth(idx)
+        .map_or(undivided_name.len(), |(byte_idx, _)| byte_idx);
+    undivided_name.split_at(byte_idx)
+}
+
+/// Orders raw split scores best first, with NaN last. The sort is stable, so equal
+/// scores keep the earlier split first.
+fn compare_scores(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+        (a_is_nan, b_is_nan) => a_is_nan.cmp(&b_is_nan),
     }
-    (family, given)
 }
 
+/// Variation selectors (including the IVS range U+E0100..U+E01EF) and combining marks,
+/// which belong to the char before them and never start a unit of their own.
+pub fn is_unit_extender(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{3099}'..='\u{309A}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{E0100}'..='\u{E01EF}'
+    )
+}
+
+/// Holds a name together with the form that is actually divided.
+///
+/// The name is split into units of a base char followed by any variation selectors
+/// and combining marks, and `normalized_name` has one char per unit: the base char,
+/// with old glyph forms replaced when normalizing. Division happens on `normalized_name`
+/// and is mapped back onto `original_name`, so a selector always stays with its kanji.
 pub struct UndividedNameHolder {
     pub original_name: String,
     pub normalized_name: String,
+    /// Char and byte offsets in `original_name` where each char of `normalized_name`
+    /// starts, followed by the end of `original_name`.
+    original_offsets: Vec<(usize, usize)>,
 }
 
 impl UndividedNameHolder {
     pub fn new(original_name: String) -> Self {
-        let mut normalized_name = original_name.clone();
-        let old_new_pairs = [("髙", "高"), ("𠮷", "吉")];
-        for (old, new) in old_new_pairs {
-            normalized_name = normalized_name.replace(old, new);
+        Self::new_with_normalization(original_name, true, None)
+    }
+
+    /// Like `new`, but only folds selectors and combining marks unless `normalize_name`.
+    /// Gaiji in `gaiji_mapping` are replaced by their standard char either way.
+    pub fn new_with_normalization(
+        original_name: String,
+        normalize_name: bool,
+        gaiji_mapping: Option<&GaijiMapping>,
+    ) -> Self {
+        let old_new_pairs = [('髙', '高'), ('𠮷', '吉')];
+        let mut normalized_name = String::with_capacity(original_name.len());
+        let mut original_offsets = Vec::new();
+        for (char_idx, (byte_idx, c)) in original_name.char_indices().enumerate() {
+            if char_idx > 0 && is_unit_extender(c) {
+                continue;
+            }
+            original_offsets.push((char_idx, byte_idx));
+            let c = gaiji_mapping.map_or(c, |gaiji_mapping| gaiji_mapping.map_char(c));
+            let normalized_char = if normalize_name {
+                old_new_pairs
+                    .iter()
+                    .find(|(old, _)| *old == c)
+                    .map_or(c, |(_, new)| *new)
+            } else {
+                c
+            };
+            normalized_name.push(normalized_char);
         }
+        original_offsets.push((original_name.chars().count(), original_name.len()));
         Self {
             original_name,
             normalized_name,
+            original_offsets,
         }
     }
 
     pub fn get_divided_original_name(&self, divided_normalized_name: DividedName) -> DividedName {
         let family_length = divided_normalized_name.family.chars().count();
-        let (family, given) = slice(&self.original_name, family_length);
+        let (family_char_end, family_byte_end) = self.original_offsets[family_length];
+        let (char_end, byte_end) = self.original_offsets[self.original_offsets.len() - 1];
+        #[allow(deprecated)]
         DividedName {
-            family,
-            given,
+            family: self.original_name[..family_byte_end].to_string(),
+            given: self.original_name[family_byte_end..].to_string(),
             separator: divided_normalized_name.separator,
             score: divided_normalized_name.score,
+            provenance: divided_normalized_name.provenance,
+            normalized_family: divided_normalized_name.family,
+            normalized_given: divided_normalized_name.given,
+            family_span: Span {
+                char_start: 0,
+                char_end: family_char_end,
+                byte_start: 0,
+                byte_end: family_byte_end,
+            },
+            given_span: Span {
+                char_start: family_char_end,
+                char_end,
+                byte_start: family_byte_end,
+                byte_end,
+            },
             algorithm: divided_normalized_name.algorithm,
         }
     }
@@ -49,17 +127,24 @@ impl UndividedNameHolder {
 pub struct NameDividerBase {
     pub separator: String,
     pub normalize_name: bool,
-    pub algorithm_name: String,
+    /// Attributed to every split decided by the score calculator.
+    pub provenance: Provenance,
     pub compiled_regex_kanji: Regex,
+    /// Gaiji are divided as their standard chars but kept as is in the output.
+    pub gaiji_mapping: Option<Arc<GaijiMapping>>,
+    /// Names divided as the dictionary says, whatever the rules and scores say.
+    pub user_dictionary: Option<Arc<UserDictionary>>,
 }
 
 impl NameDividerBase {
-    pub fn new(separator: String, normalize_name: bool, algorithm_name: String) -> Self {
+    pub fn new(separator: String, normalize_name: bool, provenance: Provenance) -> Self {
         Self {
             separator,
             normalize_name,
-            algorithm_name,
+            provenance,
             compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+            gaiji_mapping: None,
+            user_dictionary: None,
         }
     }
 
@@ -68,84 +153,255 @@ impl NameDividerBase {
         family: String,
         given: String,
         score: f64,
-        algorithm: String,
+        provenance: Provenance,
     ) -> DividedName {
+        let family_span = Span {
+            char_start: 0,
+            char_end: family.chars().count(),
+            byte_start: 0,
+            byte_end: family.len(),
+        };
+        let given_span = Span {
+            char_start: family_span.char_end,
+            char_end: family_span.char_end + given.chars().count(),
+            byte_start: family_span.byte_end,
+            byte_end: family_span.byte_end + given.len(),
+        };
+        #[allow(deprecated)]
         DividedName {
+            normalized_family: family.clone(),
+            normalized_given: given.clone(),
             family,
             given,
             separator: self.separator.clone(),
             score,
-            algorithm,
+            algorithm: provenance.algorithm_name(),
+            provenance,
+            family_span,
+            given_span,
         }
     }
 
-    fn validate(&self, undivided_name: &String) -> Result<(), &'static str> {
+    fn validate(&self, undivided_name: &str) -> Result<(), &'static str> {
         if undivided_name.chars().count() < 2 {
             return Err("Name length needs at least 2 chars.");
         }
         Ok(())
     }
 
-    fn divide_by_rule_base(&self, undivided_name: &String) -> Option<DividedName> {
+    fn divide_by_rule_base(&self, undivided_name: &str) -> Option<DividedName> {
         let fullname_length = undivided_name.chars().count();
         if fullname_length == 2 {
+            let (family, given) = slice(undivided_name, 1);
             return Some(self.create_divided_name(
-                undivided_name.chars().next().unwrap().to_string(),
-                undivided_name.chars().nth(1).unwrap().to_string(),
+                family.to_string(),
+                given.to_string(),
                 1.0,
-                "rule".to_string(),
+                Provenance::Rule {
+                    rule_id: RuleId::TwoChars,
+                },
             ));
         }
 
-        let mut is_kanji_list: Vec<bool> = Vec::new();
+        let mut buf = [0; 4];
+        let mut first_is_kanji = false;
+        let mut prev_is_kanji = false;
         for (i, c) in undivided_name.chars().enumerate() {
-            let is_kanji: bool = self.compiled_regex_kanji.is_match(&c.to_string());
-            is_kanji_list.push(is_kanji);
-            if i >= 2
-                && is_kanji_list[0] != is_kanji
-                && is_kanji_list[is_kanji_list.len() - 2] == is_kanji
-            {
+            let is_kanji: bool = self.compiled_regex_kanji.is_match(c.encode_utf8(&mut buf));
+            if i == 0 {
+                first_is_kanji = is_kanji;
+            }
+            if i >= 2 && first_is_kanji != is_kanji && prev_is_kanji == is_kanji {
                 let (family, given) = slice(undivided_name, i - 1);
-                return Some(self.create_divided_name(family, given, 1.0, "rule".to_string()));
+                return Some(self.create_divided_name(
+                    family.to_string(),
+                    given.to_string(),
+                    1.0,
+                    Provenance::Rule {
+                        rule_id: RuleId::ScriptBoundary,
+                    },
+                ));
             }
+            prev_is_kanji = is_kanji;
         }
 
         None
     }
 
+    fn divide_by_user_dictionary(&self, holder: &UndividedNameHolder) -> Option<DividedName> {
+        let family_length = self.user_dictionary.as_ref()?.get(&holder.original_name)?;
+        if family_length == 0 || family_length >= holder.normalized_name.chars().count() {
+            return None;
+        }
+        let (family, given) = slice(&holder.normalized_name, family_length);
+        Some(self.create_divided_name(
+            family.to_string(),
+            given.to_string(),
+            1.0,
+            Provenance::UserDictionary,
+        ))
+    }
+
+    /// Divides a name that already has exactly one separator in it, such as "菅 義偉",
+    /// at that separator. A half-width space, a full-width space and the output separator
+    /// count as separators.
+    fn divide_by_separator(&self, undivided_name: &str) -> Option<DividedName> {
+        let mut separators: Vec<(usize, usize)> = undivided_name
+            .char_indices()
+            .filter(|(_, c)| *c == ' ' || *c == '\u{3000}')
+            .map(|(byte_idx, c)| (byte_idx, byte_idx + c.len_utf8()))
+            .collect();
+        if !self.separator.is_empty() {
+            separators.extend(
+                undivided_name
+                    .match_indices(self.separator.as_str())
+                    .map(|(byte_idx, separator)| (byte_idx, byte_idx + separator.len())),
+            );
+            separators.sort_unstable();
+            separators.dedup();
+        }
+        let (byte_start, byte_end) = match separators[..] {
+            [separator] => separator,
+            _ => return None,
+        };
+        let family = &undivided_name[..byte_start];
+        let given = &undivided_name[byte_end..];
+        if family.is_empty() || given.is_empty() {
+            return None;
+        }
+        let normalize = |part: &str| {
+            UndividedNameHolder::new_with_normalization(
+                part.to_string(),
+                self.normalize_name,
+                self.gaiji_mapping.as_deref(),
+            )
+            .normalized_name
+        };
+        let family_char_end = family.chars().count();
+        let given_char_start = undivided_name[..byte_end].chars().count();
+        #[allow(deprecated)]
+        Some(DividedName {
+            normalized_family: normalize(family),
+            normalized_given: normalize(given),
+            family: family.to_string(),
+            given: given.to_string(),
+            separator: self.separator.clone(),
+            score: 1.0,
+            algorithm: Provenance::PreSeparated.algorithm_name(),
+            provenance: Provenance::PreSeparated,
+            family_span: Span {
+                char_start: 0,
+                char_end: family_char_end,
+                byte_start: 0,
+                byte_end: byte_start,
+            },
+            given_span: Span {
+                char_start: given_char_start,
+                char_end: given_char_start + given.chars().count(),
+                byte_start: byte_end,
+                byte_end: undivided_name.len(),
+            },
+        })
+    }
+
     fn divide_by_algorithm(
         &self,
-        undivided_name: &String,
+        undivided_name: &str,
         score_calculator: &impl ScoreCalculator,
     ) -> DividedName {
-        let mut scores: Vec<f64> = Vec::new();
-        let fullname_length = undivided_name.chars().count();
-        for idx in 1..fullname_length {
-            let (family, given) = slice(undivided_name, idx);
-            let score = score_calculator.calc_score(&family, &given);
-            scores.push(score);
-        }
-
+        // Every split is scored on borrowed slices of the name, so nothing is allocated
+        // until the winning split is turned into a DividedName.
         let mut sum = 0.0;
-        let mut max_idx = 0;
-        let mut max_val = 0.0;
-        for (i, _score) in scores.into_iter().enumerate() {
-            let _exp = _score.exp();
-            if max_val < _exp {
-                max_val = _exp;
-                max_idx = i;
+        let mut max_byte_idx = undivided_name.chars().next().map_or(0, char::len_utf8);
+        let mut max_score = f64::NEG_INFINITY;
+        for (byte_idx, _) in undivided_name.char_indices().skip(1) {
+            let (family, given) = undivided_name.split_at(byte_idx);
+            let score = score_calculator.calc_score(family, given);
+            // Same order as `compare_scores`: NaN never wins and ties keep the earlier split.
+            if score > max_score {
+                max_score = score;
+                max_byte_idx = byte_idx;
             }
-            sum += _score.exp();
+            sum += score.exp();
         }
-        let score = max_val / sum;
-        let (pred_family, pred_given) = slice(undivided_name, max_idx + 1);
+        let (pred_family, pred_given) = undivided_name.split_at(max_byte_idx);
+        let (pred_family, pred_given) = (pred_family.to_string(), pred_given.to_string());
+        if !(sum > 0.0 && sum.is_finite()) {
+            return self.create_divided_name(pred_family, pred_given, 0.0, Provenance::Fallback);
+        }
+        let score = max_score.exp() / sum;
+
+        self.create_divided_name(pred_family, pred_given, score, self.provenance.clone())
+    }
+
+    /// Every split of the name, most probable first, with the same scores as
+    /// `divide_by_algorithm`. Ties keep the earlier split first, as it wins there.
+    ///
+    /// When the scores give no probabilities, every split is a `Fallback` with score 0,
+    /// still ranked by its raw score so that the first one is what `divide_by_algorithm`
+    /// takes.
+    fn score_all_splits(
+        &self,
+        undivided_name: &str,
+        score_calculator: &impl ScoreCalculator,
+    ) -> Vec<DividedName> {
+        let mut scores: Vec<(usize, f64)> = undivided_name
+            .char_indices()
+            .skip(1)
+            .map(|(byte_idx, _)| {
+                let (family, given) = undivided_name.split_at(byte_idx);
+                (byte_idx, score_calculator.calc_score(family, given))
+            })
+            .collect();
+        let sum: f64 = scores.iter().map(|(_, score)| score.exp()).sum();
+        let is_valid = sum > 0.0 && sum.is_finite();
+        scores.sort_by(|(_, a), (_, b)| compare_scores(*a, *b));
+        scores
+            .into_iter()
+            .map(|(byte_idx, score)| {
+                let (family, given) = undivided_name.split_at(byte_idx);
+                let (score, provenance) = if is_valid {
+                    (score.exp() / sum, self.provenance.clone())
+                } else {
+                    (0.0, Provenance::Fallback)
+                };
+                self.create_divided_name(family.to_string(), given.to_string(), score, provenance)
+            })
+            .collect()
+    }
 
-        self.create_divided_name(pred_family, pred_given, score, self.algorithm_name.clone())
+    /// Returns None when the rule base divides the name, since no score is involved then.
+    pub fn gold_split_candidates(&self, gold_name: &GoldName) -> Option<GoldSplitCandidates> {
+        let undivided_name = UndividedNameHolder::new_with_normalization(
+            gold_name.undivided_name(),
+            self.normalize_name,
+            self.gaiji_mapping.as_deref(),
+        )
+        .normalized_name;
+        let family_length =
+            UndividedNameHolder::new_with_normalization(gold_name.family.clone(), false, None)
+                .normalized_name
+                .chars()
+                .count();
+        if self.divide_by_rule_base(&undivided_name).is_some() {
+            return None;
+        }
+        let fullname_length = undivided_name.chars().count();
+        let candidates = (1..fullname_length)
+            .map(|idx| {
+                let (family, given) = slice(&undivided_name, idx);
+                (family.to_string(), given.to_string())
+            })
+            .collect();
+        Some(GoldSplitCandidates {
+            candidates,
+            gold_idx: family_length - 1,
+        })
     }
 
     fn _divide_name(
         &self,
-        undivided_name: &String,
+        undivided_name: &str,
         score_calculator: &impl ScoreCalculator,
     ) -> DividedName {
         let divided_name_by_rule_base = self.divide_by_rule_base(undivided_name);
@@ -160,13 +416,63 @@ impl NameDividerBase {
         undivided_name: &String,
         score_calculator: &impl ScoreCalculator,
     ) -> DividedName {
-        self.validate(undivided_name).unwrap();
-        if self.normalize_name {
-            let holder = UndividedNameHolder::new(undivided_name.clone());
-            let divided_name = self._divide_name(&holder.normalized_name, score_calculator);
-            holder.get_divided_original_name(divided_name)
-        } else {
-            self._divide_name(undivided_name, score_calculator)
+        self.try_divide_name(undivided_name, score_calculator).unwrap()
+    }
+
+    /// Like `divide_name`, but a name of fewer than 2 units is an error instead of a panic.
+    pub fn try_divide_name(
+        &self,
+        undivided_name: &str,
+        score_calculator: &impl ScoreCalculator,
+    ) -> Result<DividedName, &'static str> {
+        if let Some(divided_name) = self.divide_by_separator(undivided_name) {
+            return Ok(divided_name);
+        }
+        let holder = UndividedNameHolder::new_with_normalization(
+            undivided_name.to_string(),
+            self.normalize_name,
+            self.gaiji_mapping.as_deref(),
+        );
+        self.validate(&holder.normalized_name)?;
+        let divided_name = match self.divide_by_user_dictionary(&holder) {
+            Some(divided_name) => divided_name,
+            None => self._divide_name(&holder.normalized_name, score_calculator),
+        };
+        Ok(holder.get_divided_original_name(divided_name))
+    }
+
+    pub fn divide_name_candidates(
+        &self,
+        undivided_name: &str,
+        score_calculator: &impl ScoreCalculator,
+    ) -> Vec<DividedName> {
+        self.try_divide_name_candidates(undivided_name, score_calculator).unwrap()
+    }
+
+    pub fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+        score_calculator: &impl ScoreCalculator,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        if let Some(divided_name) = self.divide_by_separator(undivided_name) {
+            return Ok(vec![divided_name]);
         }
+        let holder = UndividedNameHolder::new_with_normalization(
+            undivided_name.to_string(),
+            self.normalize_name,
+            self.gaiji_mapping.as_deref(),
+        );
+        self.validate(&holder.normalized_name)?;
+        let candidates = match self
+            .divide_by_user_dictionary(&holder)
+            .or_else(|| self.divide_by_rule_base(&holder.normalized_name))
+        {
+            Some(divided_name) => vec![divided_name],
+            None => self.score_all_splits(&holder.normalized_name, score_calculator),
+        };
+        Ok(candidates
+            .into_iter()
+            .map(|divided_name| holder.get_divided_original_name(divided_name))
+            .collect())
     }
 }
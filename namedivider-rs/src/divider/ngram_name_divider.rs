@@ -0,0 +1,63 @@
+use crate::divider::divided_name::DividedName;
+use crate::divider::name_divider::NameDivider;
+use crate::divider::name_divider_base::NameDividerBase;
+use crate::divider::ngram_score_calculator::NgramScoreCalculator;
+use crate::divider::provenance::Provenance;
+use crate::feature::ngram::NgramStatisticsRepository;
+use regex::Regex;
+
+pub struct NgramNameDivider {
+    pub ngram_score_calculator: NgramScoreCalculator,
+    pub name_divider_base: NameDividerBase,
+}
+
+impl NameDivider for NgramNameDivider {
+    fn divide_name(&self, undivided_name: &String) -> DividedName {
+        self.name_divider_base
+            .divide_name(undivided_name, &self.ngram_score_calculator)
+    }
+
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str> {
+        self.name_divider_base
+            .try_divide_name(undivided_name, &self.ngram_score_calculator)
+    }
+
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        self.name_divider_base
+            .divide_name_candidates(undivided_name, &self.ngram_score_calculator)
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.name_divider_base
+            .try_divide_name_candidates(undivided_name, &self.ngram_score_calculator)
+    }
+}
+
+pub fn get_ngram_name_divider(
+    separator: String,
+    normalize_name: bool,
+    ngram_statistics_repository: NgramStatisticsRepository,
+) -> NgramNameDivider {
+    let ngram_score_calculator = NgramScoreCalculator {
+        ngram_statistics_repository,
+        smoothing: 1.0,
+    };
+    let name_divider_base = NameDividerBase {
+        separator,
+        normalize_name,
+        provenance: Provenance::Model {
+            name: "ngram".to_string(),
+            version: None,
+        },
+        compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+        gaiji_mapping: None,
+        user_dictionary: None,
+    };
+    NgramNameDivider {
+        ngram_score_calculator,
+        name_divider_base,
+    }
+}
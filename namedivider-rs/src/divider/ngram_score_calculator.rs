@@ -0,0 +1,42 @@
+use crate::divider::score_calculator::ScoreCalculator;
+use crate::feature::ngram::{NgramStatisticsRepository, NGRAM_SIZES};
+
+pub struct NgramScoreCalculator {
+    pub ngram_statistics_repository: NgramStatisticsRepository,
+    pub smoothing: f64,
+}
+
+impl NgramScoreCalculator {
+    /// Log-likelihood ratio of the boundary falling at `boundary_idx` of the n-gram,
+    /// against a uniform guess over the n-gram's boundary positions and "no boundary".
+    /// Unknown n-grams carry no evidence and score 0.
+    fn calc_ngram_score(&self, ngram: &str, boundary_idx: usize, ngram_size: usize) -> f64 {
+        let statistics = match self.ngram_statistics_repository.get(ngram) {
+            Some(statistics) => statistics,
+            None => return 0.0,
+        };
+        let num_outcomes = ngram_size as f64;
+        let probability = (statistics.boundary_counts[boundary_idx] as f64 + self.smoothing)
+            / (statistics.total_count() as f64 + self.smoothing * num_outcomes);
+        (probability * num_outcomes).ln()
+    }
+}
+
+impl ScoreCalculator for NgramScoreCalculator {
+    fn calc_score(&self, family: &str, given: &str) -> f64 {
+        let chars: Vec<char> = family.chars().chain(given.chars()).collect();
+        let boundary = family.chars().count();
+        let mut score = 0.0;
+        for ngram_size in NGRAM_SIZES {
+            let first_start = boundary.saturating_sub(ngram_size - 1);
+            for start in first_start..boundary {
+                if start + ngram_size > chars.len() {
+                    break;
+                }
+                let ngram: String = chars[start..start + ngram_size].iter().collect();
+                score += self.calc_ngram_score(&ngram, boundary - start - 1, ngram_size);
+            }
+        }
+        score
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const EMBEDDED_GBDT_MODEL_VERSION: &str = "v1";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleId {
+    /// A two-char name is always divided into one char each.
+    TwoChars,
+    /// The name is divided where it switches between kanji and other scripts.
+    ScriptBoundary,
+}
+
+impl RuleId {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleId::TwoChars => "two_chars",
+            RuleId::ScriptBoundary => "script_boundary",
+        }
+    }
+}
+
+/// What decided the split of a `DividedName`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Provenance {
+    Rule {
+        rule_id: RuleId,
+    },
+    UserDictionary,
+    /// The input already had a separator between the family and the given name.
+    PreSeparated,
+    Basic,
+    Gbdt {
+        model_version: String,
+    },
+    Model {
+        name: String,
+        version: Option<String>,
+    },
+    /// The scores of the splits gave no probabilities, e.g. because they overflowed, so
+    /// the split with the highest score was taken with score 0. Splits scored NaN are
+    /// never taken unless all are, and then the first split is.
+    Fallback,
+}
+
+impl Provenance {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Provenance::Rule { .. } => "rule",
+            Provenance::UserDictionary => "user_dictionary",
+            Provenance::PreSeparated => "pre_separated",
+            Provenance::Basic => "basic",
+            Provenance::Gbdt { .. } => "gbdt",
+            Provenance::Model { .. } => "model",
+            Provenance::Fallback => "fallback",
+        }
+    }
+
+    /// The string that used to be stored in `DividedName.algorithm`.
+    pub fn algorithm_name(&self) -> String {
+        match self {
+            Provenance::Basic => "kanji_feature".to_string(),
+            Provenance::Model { name, .. } => name.clone(),
+            _ => self.kind().to_string(),
+        }
+    }
+
+    /// The provenance of a divider built with an `algorithm_name` argument, which is
+    /// what the old constructors took: `default` if it already reports that name,
+    /// otherwise a model of that name.
+    pub fn with_algorithm_name(default: Provenance, algorithm_name: String) -> Provenance {
+        if default.algorithm_name() == algorithm_name {
+            default
+        } else {
+            Provenance::Model {
+                name: algorithm_name,
+                version: None,
+            }
+        }
+    }
+
+    /// The kind and the details of the provenance as flat key/value pairs.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attributes = vec![("kind", self.kind().to_string())];
+        match self {
+            Provenance::Rule { rule_id } => {
+                attributes.push(("rule_id", rule_id.as_str().to_string()))
+            }
+            Provenance::Gbdt { model_version } => {
+                attributes.push(("model_version", model_version.clone()))
+            }
+            Provenance::Model { name, version } => {
+                attributes.push(("name", name.clone()));
+                if let Some(version) = version {
+                    attributes.push(("version", version.clone()));
+                }
+            }
+            _ => {}
+        }
+        attributes
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.algorithm_name())
+    }
+}
@@ -1,3 +1,3 @@
 pub trait ScoreCalculator {
-    fn calc_score(&self, family: &String, given: &String) -> f64;
+    fn calc_score(&self, family: &str, given: &str) -> f64;
 }
@@ -1,6 +1,7 @@
 use crate::divider::divided_name::DividedName;
 use crate::divider::name_divider::NameDivider;
 use crate::divider::name_divider_base::NameDividerBase;
+use crate::divider::provenance::Provenance;
 use crate::divider::two_char_score_calculator::TwoCharScoreCalculator;
 use regex::Regex;
 
@@ -9,27 +10,64 @@ pub struct TwoCharNameDivider {
     pub name_divider_base: NameDividerBase,
 }
 
+impl TwoCharNameDivider {
+    pub fn new(separator: String, normalize_name: bool) -> Self {
+        let two_char_score_calculator = TwoCharScoreCalculator {};
+        let name_divider_base = NameDividerBase {
+            separator,
+            normalize_name,
+            provenance: Provenance::Model {
+                name: "two_char".to_string(),
+                version: None,
+            },
+            compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
+            gaiji_mapping: None,
+            user_dictionary: None,
+        };
+        Self {
+            two_char_score_calculator,
+            name_divider_base,
+        }
+    }
+}
+
 impl NameDivider for TwoCharNameDivider {
     fn divide_name(&self, undivided_name: &String) -> DividedName {
         self.name_divider_base
             .divide_name(undivided_name, &self.two_char_score_calculator)
     }
+
+    fn try_divide_name(&self, undivided_name: &str) -> Result<DividedName, &'static str> {
+        self.name_divider_base
+            .try_divide_name(undivided_name, &self.two_char_score_calculator)
+    }
+
+    fn divide_name_candidates(&self, undivided_name: &String) -> Vec<DividedName> {
+        self.name_divider_base
+            .divide_name_candidates(undivided_name, &self.two_char_score_calculator)
+    }
+
+    fn try_divide_name_candidates(
+        &self,
+        undivided_name: &str,
+    ) -> Result<Vec<DividedName>, &'static str> {
+        self.name_divider_base
+            .try_divide_name_candidates(undivided_name, &self.two_char_score_calculator)
+    }
 }
 
+#[deprecated(
+    note = "use `TwoCharNameDivider::new`; the algorithm is told by `DividedName.provenance`"
+)]
 pub fn get_two_char_name_divider(
     separator: String,
     normalize_name: bool,
     algorithm_name: String,
 ) -> TwoCharNameDivider {
-    let two_char_score_calculator = TwoCharScoreCalculator {};
-    let name_divider_base = NameDividerBase {
-        separator,
-        normalize_name,
+    let mut divider = TwoCharNameDivider::new(separator, normalize_name);
+    divider.name_divider_base.provenance = Provenance::with_algorithm_name(
+        divider.name_divider_base.provenance.clone(),
         algorithm_name,
-        compiled_regex_kanji: Regex::new(r"\p{Script=Han}+").unwrap(),
-    };
-    TwoCharNameDivider {
-        two_char_score_calculator,
-        name_divider_base,
-    }
+    );
+    divider
 }
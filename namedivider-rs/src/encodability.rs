@@ -0,0 +1,227 @@
+use crate::divider::divided_name::DividedName;
+use crate::divider::name_divider_base::is_unit_extender;
+use crate::feature::gaiji::{is_private_use, parse_chars};
+use encoding_rs::SHIFT_JIS;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Common name kanji outside JIS X 0208 (and partly outside CP932), with the form
+/// that legacy systems expect instead.
+const BUILTIN_VARIANTS: [(char, &str); 17] = [
+    ('髙', "高"),
+    ('𠮷', "吉"),
+    ('\u{FA11}', "崎"),
+    ('德', "徳"),
+    ('濵', "浜"),
+    ('栁', "柳"),
+    ('淸', "清"),
+    ('瀨', "瀬"),
+    ('靑', "青"),
+    ('槗', "橋"),
+    ('曻', "昇"),
+    ('桒', "桑"),
+    ('\u{FA10}', "塚"),
+    ('\u{FA19}', "神"),
+    ('\u{FA1B}', "福"),
+    ('\u{F9DC}', "隆"),
+    ('𡈽', "土"),
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingTarget {
+    /// Shift_JIS as extended by Windows (Windows-31J), including the NEC and IBM extensions.
+    Cp932,
+    /// The JIS X 0208 character set: rows 1-8 and 16-84, plus ASCII.
+    JisX0208,
+}
+
+impl FromStr for EncodingTarget {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "cp932" | "shiftjis" | "sjis" | "windows31j" => Ok(EncodingTarget::Cp932),
+            "jisx0208" | "jis" => Ok(EncodingTarget::JisX0208),
+            _ => Err("Encoding target must be 'cp932' or 'jisx0208'."),
+        }
+    }
+}
+
+impl fmt::Display for EncodingTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingTarget::Cp932 => write!(f, "CP932"),
+            EncodingTarget::JisX0208 => write!(f, "JIS X 0208"),
+        }
+    }
+}
+
+/// Substitutes suggested for chars that the target encoding cannot represent.
+pub struct VariantTable {
+    variants: HashMap<char, String>,
+}
+
+impl Default for VariantTable {
+    fn default() -> Self {
+        Self {
+            variants: BUILTIN_VARIANTS
+                .iter()
+                .map(|(variant, standard)| (*variant, standard.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl VariantTable {
+    /// Reads one variant per line: the char, a tab, and its substitute, in the same
+    /// format as a gaiji mapping. Entries are added on top of the built-in ones.
+    pub fn from_text(contents: &str) -> Result<Self, &'static str> {
+        let mut variant_table = Self::default();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (variant, substitute) = line
+                .split_once('\t')
+                .ok_or("Variant table line needs a tab between the char and its substitute.")?;
+            let variant = parse_chars(variant.trim())?;
+            let mut variant_chars = variant.chars();
+            let variant = match (variant_chars.next(), variant_chars.next()) {
+                (Some(variant), None) => variant,
+                _ => return Err("Variant must be a single char."),
+            };
+            variant_table
+                .variants
+                .insert(variant, parse_chars(substitute.trim())?);
+        }
+        Ok(variant_table)
+    }
+
+    pub fn get(&self, variant: char) -> Option<&str> {
+        self.variants
+            .get(&variant)
+            .map(|standard| standard.as_str())
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnencodableChar {
+    #[serde(rename = "char")]
+    pub c: char,
+    /// `U+XXXX` notation, since the char itself may not display.
+    pub code_point: String,
+    /// Index of the char in the family or the given name.
+    pub char_index: usize,
+    /// What to write instead; an empty string means the char can be dropped.
+    pub substitute: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EncodabilityReport {
+    pub target: EncodingTarget,
+    pub family: Vec<UnencodableChar>,
+    pub given: Vec<UnencodableChar>,
+}
+
+impl EncodabilityReport {
+    pub fn is_encodable(&self) -> bool {
+        self.family.is_empty() && self.given.is_empty()
+    }
+}
+
+/// Whether `c` can be written in `target`. Chars of the user-defined area are
+/// treated as unencodable since their meaning depends on the system.
+pub fn is_encodable(c: char, target: EncodingTarget) -> bool {
+    if is_private_use(c) {
+        return false;
+    }
+    let mut buf = [0; 4];
+    let (bytes, _, had_errors) = SHIFT_JIS.encode(c.encode_utf8(&mut buf));
+    if had_errors {
+        return false;
+    }
+    match target {
+        EncodingTarget::Cp932 => true,
+        EncodingTarget::JisX0208 => match bytes.as_ref() {
+            [byte] => *byte < 0x80,
+            [lead, trail] => {
+                let lead_offset = if *lead < 0xA0 { 0x81 } else { 0xC1 };
+                let row = (u32::from(*lead) - lead_offset) * 2 + 1 + u32::from(*trail >= 0x9F);
+                (1..=8).contains(&row) || (16..=84).contains(&row)
+            }
+            _ => false,
+        },
+    }
+}
+
+fn find_unencodable_chars(
+    piece_of_divided_name: &str,
+    target: EncodingTarget,
+    variant_table: &VariantTable,
+) -> Vec<UnencodableChar> {
+    piece_of_divided_name
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !is_encodable(*c, target))
+        .map(|(char_index, c)| {
+            let substitute = if is_unit_extender(c) {
+                Some("".to_string())
+            } else {
+                variant_table.get(c).map(|standard| standard.to_string())
+            };
+            UnencodableChar {
+                c,
+                code_point: format!("U+{:04X}", c as u32),
+                char_index,
+                substitute,
+            }
+        })
+        .collect()
+}
+
+/// Reports the chars of the family and the given name that `target` cannot represent.
+pub fn check_encodability(
+    divided_name: &DividedName,
+    target: EncodingTarget,
+    variant_table: &VariantTable,
+) -> EncodabilityReport {
+    EncodabilityReport {
+        target,
+        family: find_unencodable_chars(&divided_name.family, target, variant_table),
+        given: find_unencodable_chars(&divided_name.given, target, variant_table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encodability::{is_encodable, EncodingTarget, VariantTable};
+
+    #[test]
+    fn test_is_encodable() {
+        for c in ['田', '々', 'a', 'ア', '渡'] {
+            assert!(is_encodable(c, EncodingTarget::Cp932));
+            assert!(is_encodable(c, EncodingTarget::JisX0208), "{}", c);
+        }
+        // IBM extension and NEC special characters are CP932 only.
+        for c in ['髙', '﨑', '①', 'ｱ'] {
+            assert!(is_encodable(c, EncodingTarget::Cp932), "{}", c);
+            assert!(!is_encodable(c, EncodingTarget::JisX0208), "{}", c);
+        }
+        for c in ['𠮷', '\u{E0100}', '\u{E000}'] {
+            assert!(!is_encodable(c, EncodingTarget::Cp932));
+            assert!(!is_encodable(c, EncodingTarget::JisX0208));
+        }
+    }
+
+    #[test]
+    fn test_variant_table_from_text() {
+        let variant_table = VariantTable::from_text("U+FA1E\t羽\n").unwrap();
+        assert_eq!(variant_table.get('\u{FA1E}'), Some("羽"));
+        assert_eq!(variant_table.get('髙'), Some("高"));
+        assert!(VariantTable::from_text("髙橋\t高橋\n").is_err());
+    }
+}
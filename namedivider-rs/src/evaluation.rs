@@ -0,0 +1,284 @@
+use crate::corpus::GoldName;
+use crate::divider::divided_name::DividedName;
+use crate::divider::name_divider::NameDivider;
+use crate::divider::name_divider_base::is_unit_extender;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How a divider divided one gold name.
+pub struct NameEvaluation {
+    pub gold_name: GoldName,
+    pub divided_name: DividedName,
+    /// 1-based rank of the gold split among the candidates, when it is within the top k.
+    pub gold_rank: Option<usize>,
+}
+
+impl NameEvaluation {
+    pub fn is_correct(&self) -> bool {
+        self.divided_name.family == self.gold_name.family
+            && self.divided_name.given == self.gold_name.given
+    }
+}
+
+/// Divides the gold name and, when the division is wrong, looks for the gold split
+/// among the top `top_k` candidates.
+pub fn evaluate_name(
+    divider: &dyn NameDivider,
+    gold_name: GoldName,
+    top_k: usize,
+) -> NameEvaluation {
+    let undivided_name = gold_name.undivided_name();
+    let divided_name = divider.divide_name(&undivided_name);
+    let gold_rank = if divided_name.family == gold_name.family {
+        Some(1)
+    } else if top_k > 1 {
+        divider
+            .divide_name_candidates(&undivided_name)
+            .iter()
+            .take(top_k)
+            .position(|candidate| candidate.family == gold_name.family)
+            .map(|idx| idx + 1)
+    } else {
+        None
+    };
+    NameEvaluation {
+        gold_name,
+        divided_name,
+        gold_rank,
+    }
+}
+
+/// Length of a name in the units it is divided on.
+fn num_units(piece_of_divided_name: &str) -> usize {
+    piece_of_divided_name
+        .chars()
+        .filter(|c| !is_unit_extender(*c))
+        .count()
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AccuracyBucket {
+    pub label: String,
+    pub num_names: usize,
+    pub num_correct: usize,
+    pub accuracy: f64,
+}
+
+/// Names whose score is at least `threshold`: their share of all names and accuracy.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CoveragePoint {
+    pub threshold: f64,
+    pub num_names: usize,
+    pub coverage: f64,
+    /// None when no name reaches the threshold.
+    pub accuracy: Option<f64>,
+}
+
+/// Names whose score is in `[lower, upper)`, or `[lower, 1.0]` for the last bin.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CalibrationBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub num_names: usize,
+    pub mean_score: Option<f64>,
+    pub accuracy: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TopKAccuracy {
+    pub k: usize,
+    pub num_correct: usize,
+    pub accuracy: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AccuracyReport {
+    pub num_names: usize,
+    pub num_correct: usize,
+    pub accuracy: f64,
+    /// By the length of the full name.
+    pub by_length: Vec<AccuracyBucket>,
+    /// By the lengths of the gold family and given name, labeled like `2+2`.
+    pub by_family_given_length: Vec<AccuracyBucket>,
+    /// By the kind of provenance of the division, such as `rule` or `gbdt`.
+    pub by_provenance: Vec<AccuracyBucket>,
+    pub coverage: Vec<CoveragePoint>,
+    pub calibration: Vec<CalibrationBin>,
+    /// Mean gap between the score and the accuracy of the calibration bins,
+    /// weighted by the number of names in each.
+    pub expected_calibration_error: f64,
+    pub top_k: Vec<TopKAccuracy>,
+}
+
+struct EvaluatedName {
+    family_length: usize,
+    given_length: usize,
+    provenance_kind: &'static str,
+    score: f64,
+    is_correct: bool,
+    gold_rank: Option<usize>,
+}
+
+/// Collects evaluated names and summarizes them into an `AccuracyReport`.
+pub struct AccuracyReportBuilder {
+    top_k: usize,
+    thresholds: Vec<f64>,
+    num_calibration_bins: usize,
+    evaluated_names: Vec<EvaluatedName>,
+}
+
+fn to_ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn to_buckets<K: ToString>(
+    counts: impl IntoIterator<Item = (K, (usize, usize))>,
+) -> Vec<AccuracyBucket> {
+    counts
+        .into_iter()
+        .map(|(label, (num_names, num_correct))| AccuracyBucket {
+            label: label.to_string(),
+            num_names,
+            num_correct,
+            accuracy: to_ratio(num_correct, num_names),
+        })
+        .collect()
+}
+
+impl AccuracyReportBuilder {
+    pub fn new(top_k: usize, thresholds: Vec<f64>, num_calibration_bins: usize) -> Self {
+        Self {
+            top_k: top_k.max(1),
+            thresholds,
+            num_calibration_bins: num_calibration_bins.max(1),
+            evaluated_names: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, name_evaluation: &NameEvaluation) {
+        self.evaluated_names.push(EvaluatedName {
+            family_length: num_units(&name_evaluation.gold_name.family),
+            given_length: num_units(&name_evaluation.gold_name.given),
+            provenance_kind: name_evaluation.divided_name.provenance.kind(),
+            score: name_evaluation.divided_name.score,
+            is_correct: name_evaluation.is_correct(),
+            gold_rank: name_evaluation.gold_rank,
+        });
+    }
+
+    pub fn build(&self) -> AccuracyReport {
+        let num_names = self.evaluated_names.len();
+        let num_correct = self.evaluated_names.iter().filter(|e| e.is_correct).count();
+
+        let mut by_length = BTreeMap::new();
+        let mut by_family_given_length = BTreeMap::new();
+        let mut by_provenance = BTreeMap::new();
+        for e in &self.evaluated_names {
+            for counts in [
+                by_length
+                    .entry(e.family_length + e.given_length)
+                    .or_insert((0, 0)),
+                by_family_given_length
+                    .entry((e.family_length, e.given_length))
+                    .or_insert((0, 0)),
+                by_provenance.entry(e.provenance_kind).or_insert((0, 0)),
+            ] {
+                counts.0 += 1;
+                counts.1 += e.is_correct as usize;
+            }
+        }
+        let by_family_given_length =
+            by_family_given_length
+                .into_iter()
+                .map(|((family_length, given_length), counts)| {
+                    (format!("{}+{}", family_length, given_length), counts)
+                });
+
+        let coverage = self
+            .thresholds
+            .iter()
+            .map(|threshold| {
+                let covered: Vec<&EvaluatedName> = self
+                    .evaluated_names
+                    .iter()
+                    .filter(|e| e.score >= *threshold)
+                    .collect();
+                let num_covered_correct = covered.iter().filter(|e| e.is_correct).count();
+                CoveragePoint {
+                    threshold: *threshold,
+                    num_names: covered.len(),
+                    coverage: to_ratio(covered.len(), num_names),
+                    accuracy: if covered.is_empty() {
+                        None
+                    } else {
+                        Some(to_ratio(num_covered_correct, covered.len()))
+                    },
+                }
+            })
+            .collect();
+
+        let num_bins = self.num_calibration_bins;
+        let mut bin_counts = vec![(0, 0, 0.0); num_bins];
+        for e in &self.evaluated_names {
+            let bin_idx = ((e.score.clamp(0.0, 1.0) * num_bins as f64) as usize).min(num_bins - 1);
+            bin_counts[bin_idx].0 += 1;
+            bin_counts[bin_idx].1 += e.is_correct as usize;
+            bin_counts[bin_idx].2 += e.score;
+        }
+        let mut expected_calibration_error = 0.0;
+        let calibration = bin_counts
+            .into_iter()
+            .enumerate()
+            .map(|(bin_idx, (num_bin_names, num_bin_correct, score_sum))| {
+                let (mean_score, accuracy) = if num_bin_names == 0 {
+                    (None, None)
+                } else {
+                    let mean_score = score_sum / num_bin_names as f64;
+                    let accuracy = to_ratio(num_bin_correct, num_bin_names);
+                    expected_calibration_error +=
+                        to_ratio(num_bin_names, num_names) * (accuracy - mean_score).abs();
+                    (Some(mean_score), Some(accuracy))
+                };
+                CalibrationBin {
+                    lower: bin_idx as f64 / num_bins as f64,
+                    upper: (bin_idx + 1) as f64 / num_bins as f64,
+                    num_names: num_bin_names,
+                    mean_score,
+                    accuracy,
+                }
+            })
+            .collect();
+
+        let top_k = (1..=self.top_k)
+            .map(|k| {
+                let num_top_k_correct = self
+                    .evaluated_names
+                    .iter()
+                    .filter(|e| e.gold_rank.is_some_and(|gold_rank| gold_rank <= k))
+                    .count();
+                TopKAccuracy {
+                    k,
+                    num_correct: num_top_k_correct,
+                    accuracy: to_ratio(num_top_k_correct, num_names),
+                }
+            })
+            .collect();
+
+        AccuracyReport {
+            num_names,
+            num_correct,
+            accuracy: to_ratio(num_correct, num_names),
+            by_length: to_buckets(by_length),
+            by_family_given_length: to_buckets(by_family_given_length),
+            by_provenance: to_buckets(by_provenance),
+            coverage,
+            calibration,
+            expected_calibration_error,
+            top_k,
+        }
+    }
+}
@@ -1,5 +1,10 @@
 pub mod assets;
+pub mod compiled_assets;
 pub mod extractor;
 pub mod family_name;
 pub mod functional;
+pub mod gaiji;
 pub mod kanji;
+pub mod ngram;
+pub mod shared_assets;
+pub mod user_dictionary;
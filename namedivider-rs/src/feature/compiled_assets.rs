@@ -0,0 +1,443 @@
+use crate::feature::kanji::KanjiStatistics;
+use memmap2::Mmap;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 4] = b"NDAS";
+const FORMAT_VERSION: u8 = 2;
+const NUM_SECTIONS: usize = 5;
+const HEADER_SIZE: usize = 8 + NUM_SECTIONS * 8;
+const NUM_ORDER_COUNTS: usize = 6;
+const NUM_LENGTH_COUNTS: usize = 8;
+const KANJI_RECORD_SIZE: usize = 4 * (1 + NUM_ORDER_COUNTS + NUM_LENGTH_COUNTS);
+const FAMILY_NAME_RECORD_SIZE: usize = 4 * 3;
+
+const KANJI_SECTION: usize = 0;
+const FAMILY_NAMES_SECTION: usize = 1;
+const FAMILY_NAME_INDEX_SECTION: usize = 2;
+const GBDT_MODEL_SECTION: usize = 3;
+const GBDT_MODEL_VERSION_SECTION: usize = 4;
+
+/// The order counts and the length counts of a kanji.
+pub type KanjiCounts = ([i32; NUM_ORDER_COUNTS], [i32; NUM_LENGTH_COUNTS]);
+
+/// Bytes of a compiled assets file, shared by the tables served from them.
+type AssetBytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+/// Kanji statistics, family names and a GBDT model packed into one file.
+///
+/// The file starts with `NDAS`, a version byte, 3 reserved bytes and a table of
+/// (offset, length) pairs for the sections below. Every integer is little-endian.
+///
+/// - kanji: fixed-size records sorted by kanji, each the code point as u32
+///   followed by the 6 order counts and the 8 length counts as i32
+/// - family names: `family_names.txt` as is, one name per line, most common first
+/// - family name index: fixed-size records sorted by name, each the offset and the
+///   length of a name in the family names section and its rank, as u32
+/// - GBDT model: the LightGBM model text, empty when the bundle has none
+/// - GBDT model version: UTF-8 text
+///
+/// Opening a bundle checks the bounds and the order of the records and validates the
+/// text sections as UTF-8; nothing is decoded. `CompiledKanjiTable` and
+/// `CompiledFamilyNameTable` keep the bytes and look kanji and family names up by
+/// binary search over the sorted records.
+pub struct CompiledAssets {
+    bytes: AssetBytes,
+    sections: [Range<usize>; NUM_SECTIONS],
+}
+
+impl CompiledAssets {
+    /// Takes the bytes of a bundle, such as the `Mmap` of `map_compiled_assets`.
+    pub fn from_bytes(
+        bytes: impl AsRef<[u8]> + Send + Sync + 'static,
+    ) -> Result<Self, &'static str> {
+        let bytes: AssetBytes = Arc::new(bytes);
+        let sections = read_sections((*bytes).as_ref())?;
+        Ok(Self { bytes, sections })
+    }
+
+    fn section(&self, section: usize) -> &[u8] {
+        &(*self.bytes).as_ref()[self.sections[section].clone()]
+    }
+
+    fn text_section(&self, section: usize) -> &str {
+        std::str::from_utf8(self.section(section)).expect("validated by from_bytes")
+    }
+
+    /// Decodes every kanji record, in kanji order.
+    pub fn kanji_statistics(&self) -> impl Iterator<Item = KanjiStatistics> + '_ {
+        self.section(KANJI_SECTION)
+            .chunks_exact(KANJI_RECORD_SIZE)
+            .map(|record| {
+                let (order_counts, length_counts) = read_kanji_counts(record);
+                KanjiStatistics {
+                    kanji: char::from_u32(read_u32(record, 0)).unwrap().to_string(),
+                    order_counts: order_counts.to_vec(),
+                    length_counts: length_counts.to_vec(),
+                }
+            })
+    }
+
+    pub fn kanji_table(&self) -> CompiledKanjiTable {
+        CompiledKanjiTable {
+            bytes: self.bytes.clone(),
+            records: self.sections[KANJI_SECTION].clone(),
+        }
+    }
+
+    pub fn family_names(&self) -> &str {
+        self.text_section(FAMILY_NAMES_SECTION)
+    }
+
+    pub fn family_name_table(&self) -> CompiledFamilyNameTable {
+        CompiledFamilyNameTable {
+            bytes: self.bytes.clone(),
+            family_names: self.sections[FAMILY_NAMES_SECTION].clone(),
+            records: self.sections[FAMILY_NAME_INDEX_SECTION].clone(),
+        }
+    }
+
+    pub fn gbdt_model(&self) -> Option<&str> {
+        let gbdt_model = self.text_section(GBDT_MODEL_SECTION);
+        if gbdt_model.is_empty() {
+            None
+        } else {
+            Some(gbdt_model)
+        }
+    }
+
+    pub fn gbdt_model_version(&self) -> &str {
+        self.text_section(GBDT_MODEL_VERSION_SECTION)
+    }
+}
+
+/// Kanji records of a bundle, looked up by binary search over their code points.
+#[derive(Clone)]
+pub struct CompiledKanjiTable {
+    bytes: AssetBytes,
+    records: Range<usize>,
+}
+
+impl CompiledKanjiTable {
+    fn records(&self) -> &[u8] {
+        &(*self.bytes).as_ref()[self.records.clone()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len() / KANJI_RECORD_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The order counts and the length counts of `kanji`, if it has a record.
+    pub fn get(&self, kanji: char) -> Option<KanjiCounts> {
+        binary_search_records(self.records(), KANJI_RECORD_SIZE, |record| {
+            read_u32(record, 0).cmp(&(kanji as u32))
+        })
+        .map(read_kanji_counts)
+    }
+}
+
+/// Family names of a bundle, looked up by binary search over the name index.
+#[derive(Clone)]
+pub struct CompiledFamilyNameTable {
+    bytes: AssetBytes,
+    family_names: Range<usize>,
+    records: Range<usize>,
+}
+
+impl CompiledFamilyNameTable {
+    fn records(&self) -> &[u8] {
+        &(*self.bytes).as_ref()[self.records.clone()]
+    }
+
+    /// Number of distinct family names.
+    pub fn len(&self) -> usize {
+        self.records.len() / FAMILY_NAME_RECORD_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn get_rank(&self, family: &str) -> Option<usize> {
+        let family_names = &(*self.bytes).as_ref()[self.family_names.clone()];
+        binary_search_records(self.records(), FAMILY_NAME_RECORD_SIZE, |record| {
+            family_name_of(family_names, record).cmp(family.as_bytes())
+        })
+        .map(|record| read_u32(record, 8) as usize)
+    }
+}
+
+/// Finds the record for which `compare` is `Equal` among records sorted by it.
+fn binary_search_records(
+    records: &[u8],
+    record_size: usize,
+    compare: impl Fn(&[u8]) -> Ordering,
+) -> Option<&[u8]> {
+    let (mut low, mut high) = (0, records.len() / record_size);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let record = &records[mid * record_size..(mid + 1) * record_size];
+        match compare(record) {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => return Some(record),
+        }
+    }
+    None
+}
+
+fn read_kanji_counts(record: &[u8]) -> KanjiCounts {
+    let mut order_counts = [0; NUM_ORDER_COUNTS];
+    for (i, count) in order_counts.iter_mut().enumerate() {
+        *count = read_u32(record, 4 * (1 + i)) as i32;
+    }
+    let mut length_counts = [0; NUM_LENGTH_COUNTS];
+    for (i, count) in length_counts.iter_mut().enumerate() {
+        *count = read_u32(record, 4 * (1 + NUM_ORDER_COUNTS + i)) as i32;
+    }
+    (order_counts, length_counts)
+}
+
+/// The bytes of the name a family name index record points to, checked by `from_bytes`
+/// to be in bounds.
+fn family_name_of<'a>(family_names: &'a [u8], record: &[u8]) -> &'a [u8] {
+    let offset = read_u32(record, 0) as usize;
+    let length = read_u32(record, 4) as usize;
+    &family_names[offset..offset + length]
+}
+
+fn read_sections(bytes: &[u8]) -> Result<[Range<usize>; NUM_SECTIONS], &'static str> {
+    if bytes.len() < HEADER_SIZE || &bytes[..4] != MAGIC {
+        return Err("Not a compiled assets file.");
+    }
+    if bytes[4] != FORMAT_VERSION {
+        return Err("Unsupported compiled assets format version.");
+    }
+    let mut sections: [Range<usize>; NUM_SECTIONS] = Default::default();
+    for (i, section) in sections.iter_mut().enumerate() {
+        let offset = read_u32(bytes, 8 + i * 8) as usize;
+        let length = read_u32(bytes, 12 + i * 8) as usize;
+        *section = offset
+            .checked_add(length)
+            .filter(|&end| end <= bytes.len())
+            .map(|end| offset..end)
+            .ok_or("Compiled assets section is out of bounds.")?;
+    }
+
+    let kanji = &bytes[sections[KANJI_SECTION].clone()];
+    if !kanji.chunks_exact(KANJI_RECORD_SIZE).remainder().is_empty() {
+        return Err("Compiled kanji statistics are truncated.");
+    }
+    let mut last_kanji = None;
+    for record in kanji.chunks_exact(KANJI_RECORD_SIZE) {
+        let code_point = read_u32(record, 0);
+        if char::from_u32(code_point).is_none() {
+            return Err("Compiled kanji statistics contain an invalid char.");
+        }
+        if last_kanji >= Some(code_point) {
+            return Err("Compiled kanji statistics are not sorted.");
+        }
+        last_kanji = Some(code_point);
+    }
+
+    for section in [
+        FAMILY_NAMES_SECTION,
+        GBDT_MODEL_SECTION,
+        GBDT_MODEL_VERSION_SECTION,
+    ] {
+        std::str::from_utf8(&bytes[sections[section].clone()])
+            .map_err(|_| "Section is not valid UTF-8.")?;
+    }
+    let family_names = &bytes[sections[FAMILY_NAMES_SECTION].clone()];
+    let family_name_index = &bytes[sections[FAMILY_NAME_INDEX_SECTION].clone()];
+    if !family_name_index
+        .chunks_exact(FAMILY_NAME_RECORD_SIZE)
+        .remainder()
+        .is_empty()
+    {
+        return Err("Compiled family name index is truncated.");
+    }
+    let mut last_family_name: Option<&[u8]> = None;
+    for record in family_name_index.chunks_exact(FAMILY_NAME_RECORD_SIZE) {
+        let offset = read_u32(record, 0) as usize;
+        let length = read_u32(record, 4) as usize;
+        let family_name = offset
+            .checked_add(length)
+            .and_then(|end| family_names.get(offset..end))
+            .ok_or("Compiled family name index is out of bounds.")?;
+        if last_family_name >= Some(family_name) {
+            return Err("Compiled family name index is not sorted.");
+        }
+        last_family_name = Some(family_name);
+    }
+    Ok(sections)
+}
+
+/// Packs the assets into the format read by `CompiledAssets::from_bytes`.
+pub fn compile_assets(
+    kanji_statistics: &[KanjiStatistics],
+    family_names: &str,
+    gbdt_model: Option<&str>,
+    gbdt_model_version: &str,
+) -> Result<Vec<u8>, &'static str> {
+    let mut records: Vec<(u32, &KanjiStatistics)> = Vec::with_capacity(kanji_statistics.len());
+    for _d in kanji_statistics {
+        let mut chars = _d.kanji.chars();
+        let kanji = match (chars.next(), chars.next()) {
+            (Some(kanji), None) => kanji,
+            _ => return Err("Kanji statistics must be keyed by a single char."),
+        };
+        if _d.order_counts.len() != NUM_ORDER_COUNTS || _d.length_counts.len() != NUM_LENGTH_COUNTS
+        {
+            return Err("Kanji statistics need 6 order counts and 8 length counts.");
+        }
+        records.push((kanji as u32, _d));
+    }
+    records.sort_by_key(|(kanji, _)| *kanji);
+    records.dedup_by_key(|(kanji, _)| *kanji);
+
+    let mut kanji = Vec::with_capacity(records.len() * KANJI_RECORD_SIZE);
+    for (code_point, _d) in records {
+        kanji.extend_from_slice(&code_point.to_le_bytes());
+        for count in _d.order_counts.iter().chain(&_d.length_counts) {
+            kanji.extend_from_slice(&count.to_le_bytes());
+        }
+    }
+
+    // A name listed twice keeps its last rank, as `FamilyNameRepository::from_text` does.
+    let mut family_name_records: Vec<(&str, usize, usize)> = vec![];
+    let mut offset = 0;
+    for (rank, line) in family_names.split_inclusive('\n').enumerate() {
+        let family_name = line.strip_suffix('\n').unwrap_or(line);
+        let family_name = family_name.strip_suffix('\r').unwrap_or(family_name);
+        family_name_records.push((family_name, offset, rank));
+        offset += line.len();
+    }
+    family_name_records.sort_by(|a, b| a.0.cmp(b.0).then(b.2.cmp(&a.2)));
+    family_name_records.dedup_by_key(|(family_name, _, _)| *family_name);
+    let mut family_name_index =
+        Vec::with_capacity(family_name_records.len() * FAMILY_NAME_RECORD_SIZE);
+    for (family_name, offset, rank) in family_name_records {
+        for value in [offset, family_name.len(), rank] {
+            let value = u32::try_from(value).map_err(|_| "Family names are too large.")?;
+            family_name_index.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let sections: [&[u8]; NUM_SECTIONS] = [
+        &kanji,
+        family_names.as_bytes(),
+        &family_name_index,
+        gbdt_model.unwrap_or("").as_bytes(),
+        gbdt_model_version.as_bytes(),
+    ];
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&[FORMAT_VERSION, 0, 0, 0]);
+    let mut offset = HEADER_SIZE;
+    for section in &sections {
+        let length = u32::try_from(section.len()).map_err(|_| "Asset is too large.")?;
+        bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+        bytes.extend_from_slice(&length.to_le_bytes());
+        offset += section.len();
+    }
+    if u32::try_from(offset).is_err() {
+        return Err("Assets are too large.");
+    }
+    for section in &sections {
+        bytes.extend_from_slice(section);
+    }
+    Ok(bytes)
+}
+
+/// Maps a file written by `compile_assets` into memory, to be read with
+/// `CompiledAssets::from_bytes`.
+pub fn map_compiled_assets(path: impl AsRef<Path>) -> std::io::Result<Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is read-only and lives as long as the repositories served from
+    // it. Truncating or rewriting the file meanwhile is not supported.
+    unsafe { Mmap::map(&file) }
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[pos..pos + 4]);
+    u32::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::compiled_assets::{compile_assets, CompiledAssets};
+    use crate::feature::kanji::KanjiStatistics;
+
+    #[test]
+    fn test_compiled_assets_round_trip() {
+        let kanji_statistics = vec![
+            KanjiStatistics {
+                kanji: "田".to_string(),
+                order_counts: vec![10, 2, 30, 0, 1, 5],
+                length_counts: vec![1, 20, 3, 0, 4, 5, 6, -7],
+            },
+            KanjiStatistics {
+                kanji: "々".to_string(),
+                order_counts: vec![0, 275, 9, 0, 14, 25],
+                length_counts: vec![0, 7, 276, 1, 0, 23, 16, 0],
+            },
+        ];
+        let bytes = compile_assets(&kanji_statistics, "佐藤\n鈴木\n", None, "v1").unwrap();
+        let compiled_assets = CompiledAssets::from_bytes(bytes.clone()).unwrap();
+        let restored: Vec<KanjiStatistics> = compiled_assets.kanji_statistics().collect();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored[0].kanji, "々");
+        assert_eq!(restored[1].order_counts, kanji_statistics[0].order_counts);
+        assert_eq!(restored[1].length_counts, kanji_statistics[0].length_counts);
+        assert_eq!(compiled_assets.family_names(), "佐藤\n鈴木\n");
+        assert_eq!(compiled_assets.gbdt_model(), None);
+        assert_eq!(compiled_assets.gbdt_model_version(), "v1");
+
+        assert!(CompiledAssets::from_bytes(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(CompiledAssets::from_bytes(b"NDNG\x01".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_compiled_tables_look_up_by_binary_search() {
+        let kanji_statistics: Vec<KanjiStatistics> = ["田", "中", "々", "𠮷"]
+            .iter()
+            .enumerate()
+            .map(|(i, kanji)| KanjiStatistics {
+                kanji: kanji.to_string(),
+                order_counts: vec![i as i32; 6],
+                length_counts: vec![-(i as i32); 8],
+            })
+            .collect();
+        let family_names = "佐藤\n鈴木\n高橋\r\n田中\n佐藤\n";
+        let bytes = compile_assets(&kanji_statistics, family_names, None, "v1").unwrap();
+        let compiled_assets = CompiledAssets::from_bytes(bytes).unwrap();
+
+        let kanji_table = compiled_assets.kanji_table();
+        assert_eq!(kanji_table.len(), 4);
+        for (i, kanji) in ['田', '中', '々', '𠮷'].into_iter().enumerate() {
+            let (order_counts, length_counts) = kanji_table.get(kanji).unwrap();
+            assert_eq!(order_counts, [i as i32; 6]);
+            assert_eq!(length_counts, [-(i as i32); 8]);
+        }
+        assert!(kanji_table.get('太').is_none());
+
+        let family_name_table = compiled_assets.family_name_table();
+        assert_eq!(family_name_table.len(), 4);
+        assert_eq!(family_name_table.get_rank("鈴木"), Some(1));
+        assert_eq!(family_name_table.get_rank("高橋"), Some(2));
+        assert_eq!(family_name_table.get_rank("田中"), Some(3));
+        // Listed twice, the name keeps its last rank.
+        assert_eq!(family_name_table.get_rank("佐藤"), Some(4));
+        assert_eq!(family_name_table.get_rank("佐"), None);
+        assert_eq!(family_name_table.get_rank(""), None);
+    }
+}
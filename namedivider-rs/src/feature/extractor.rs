@@ -1,6 +1,8 @@
 use crate::feature::family_name::FamilyNameRepository;
 use crate::feature::functional as F;
 use crate::feature::kanji::KanjiStatisticsRepository;
+use crate::feature::shared_assets;
+use std::sync::Arc;
 
 pub struct SimpleFeatures {
     pub family_order_score: f64,
@@ -21,6 +23,19 @@ pub struct FamilyRankingFeatures {
     pub given_startswith_specific_kanji: f64,
 }
 
+/// Names of the values of `FamilyRankingFeatures::to_vec`, in order.
+pub const FAMILY_RANKING_FEATURE_NAMES: [&str; 9] = [
+    "rank",
+    "fullname_length",
+    "family_length",
+    "given_length",
+    "family_order_score",
+    "given_order_score",
+    "family_length_score",
+    "given_length_score",
+    "given_startswith_specific_kanji",
+];
+
 impl FamilyRankingFeatures {
     pub fn to_vec(&self) -> Vec<f64> {
         vec![
@@ -38,12 +53,13 @@ impl FamilyRankingFeatures {
 }
 
 pub struct SimpleFeatureExtractor {
-    pub kanji_statistics_repository: KanjiStatisticsRepository,
+    pub kanji_statistics_repository: Arc<KanjiStatisticsRepository>,
 }
 
 impl SimpleFeatureExtractor {
-    pub fn get_features(&self, family: &String, given: &String) -> SimpleFeatures {
-        let fullname_length = family.chars().count() + given.chars().count();
+    pub fn get_features(&self, family: &str, given: &str) -> SimpleFeatures {
+        let family_length = family.chars().count();
+        let fullname_length = family_length + given.chars().count();
         let family_order_score = F::calc_order_score(
             &self.kanji_statistics_repository,
             family,
@@ -60,13 +76,13 @@ impl SimpleFeatureExtractor {
             &self.kanji_statistics_repository,
             given,
             fullname_length,
-            family.chars().count(),
+            family_length,
         );
         let given_length_score = F::calc_length_score(
             &self.kanji_statistics_repository,
             given,
             fullname_length,
-            family.chars().count(),
+            family_length,
         );
         SimpleFeatures {
             family_order_score,
@@ -78,12 +94,22 @@ impl SimpleFeatureExtractor {
 }
 
 pub struct FamilyRankingFeatureExtractor {
-    pub kanji_statistics_repository: KanjiStatisticsRepository,
-    pub family_name_repository: FamilyNameRepository,
+    pub kanji_statistics_repository: Arc<KanjiStatisticsRepository>,
+    pub family_name_repository: Arc<FamilyNameRepository>,
+}
+
+impl FamilyRankingFeatureExtractor {
+    /// Uses the process-wide embedded assets.
+    pub fn shared() -> Self {
+        Self {
+            kanji_statistics_repository: shared_assets::kanji_statistics_repository(),
+            family_name_repository: shared_assets::family_name_repository(),
+        }
+    }
 }
 
 impl FamilyRankingFeatureExtractor {
-    pub fn get_features(&self, family: &String, given: &String) -> FamilyRankingFeatures {
+    pub fn get_features(&self, family: &str, given: &str) -> FamilyRankingFeatures {
         let rank = self.family_name_repository.get_rank(family);
         let family_length = family.chars().count();
         let family_length_f64 = family_length as f64;
@@ -107,13 +133,13 @@ impl FamilyRankingFeatureExtractor {
             &self.kanji_statistics_repository,
             given,
             fullname_length,
-            family.chars().count(),
+            family_length,
         );
         let given_length_score = F::calc_length_score(
             &self.kanji_statistics_repository,
             given,
             fullname_length,
-            family.chars().count(),
+            family_length,
         );
 
         let mut given_startswith_specific_kanji = false;
@@ -144,12 +170,13 @@ impl FamilyRankingFeatureExtractor {
 mod tests {
     use crate::feature::extractor::SimpleFeatureExtractor;
     use crate::feature::kanji::KanjiStatisticsRepository;
+    use std::sync::Arc;
 
     #[test]
     fn test_simple_feature_extractor() {
         let family = "中曽根".to_string();
         let given = "康弘".to_string();
-        let kanji_statistics_repository = KanjiStatisticsRepository::new();
+        let kanji_statistics_repository = Arc::new(KanjiStatisticsRepository::new());
         let extractor = SimpleFeatureExtractor {
             kanji_statistics_repository,
         };
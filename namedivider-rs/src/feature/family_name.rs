@@ -1,8 +1,17 @@
 use crate::feature::assets::Asset;
+use crate::feature::compiled_assets::{CompiledAssets, CompiledFamilyNameTable};
 use std::collections::HashMap;
 
+/// Where the ranks of a `FamilyNameRepository` are looked up.
+enum FamilyNameTable {
+    /// Parsed from `family_names.txt`.
+    Parsed(HashMap<String, f64>),
+    /// Served from the name index of a compiled bundle.
+    Compiled(CompiledFamilyNameTable),
+}
+
 pub struct FamilyNameRepository {
-    family_names: HashMap<String, f64>,
+    family_names: FamilyNameTable,
 }
 
 impl FamilyNameRepository {
@@ -13,21 +22,48 @@ impl FamilyNameRepository {
             .as_ref()
             .to_owned();
         let contents_str = std::str::from_utf8(&contents).unwrap();
+        Self::from_text(contents_str)
+    }
+
+    /// One family name per line, most common first.
+    pub fn from_text(contents: &str) -> Self {
         let mut family_names = HashMap::new();
 
-        for (rank, family_name) in contents_str.lines().enumerate() {
+        for (rank, family_name) in contents.lines().enumerate() {
             family_names.insert(family_name.to_string(), rank as f64);
         }
 
-        Self { family_names }
+        Self {
+            family_names: FamilyNameTable::Parsed(family_names),
+        }
+    }
+
+    /// Looks the names up in the name index of the bundle, without building a map.
+    pub fn from_compiled(compiled_assets: &CompiledAssets) -> Self {
+        Self {
+            family_names: FamilyNameTable::Compiled(compiled_assets.family_name_table()),
+        }
     }
 
-    pub fn get_rank(&self, family: &String) -> f64 {
-        let rank = self.family_names.get(family);
-        if rank.is_none() {
-            f64::NAN
-        } else {
-            *rank.unwrap()
+    /// Number of distinct family names.
+    pub fn len(&self) -> usize {
+        match &self.family_names {
+            FamilyNameTable::Parsed(family_names) => family_names.len(),
+            FamilyNameTable::Compiled(family_name_table) => family_name_table.len(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_rank(&self, family: &str) -> f64 {
+        let rank = match &self.family_names {
+            FamilyNameTable::Parsed(family_names) => family_names.get(family).copied(),
+            FamilyNameTable::Compiled(family_name_table) => {
+                family_name_table.get_rank(family).map(|rank| rank as f64)
+            }
+        };
+        rank.unwrap_or(f64::NAN)
+    }
 }
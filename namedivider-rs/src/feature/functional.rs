@@ -1,25 +1,20 @@
 use crate::feature::kanji;
 
-fn _create_order_mask(
-    full_name_length: &usize,
-    char_idx: &usize,
-) -> Result<Vec<i32>, &'static str> {
-    if char_idx == &0 || char_idx == &(full_name_length - 1) {
-        return Err("First character and last character must not be created order mask.");
-    }
-    if full_name_length == &3 {
-        return Ok(vec![0, 0, 1, 1, 0, 0]);
-    }
-    if char_idx == &1 {
-        return Ok(vec![0, 1, 1, 1, 0, 0]);
-    }
-    if char_idx == &(full_name_length - 2) {
-        return Ok(vec![0, 0, 1, 1, 1, 0]);
+/// Index into `kanji::ORDER_MASKS` for the char at `char_idx`.
+/// The first and the last char of the full name must not be passed.
+fn _order_mask_idx(full_name_length: usize, char_idx: usize) -> usize {
+    if full_name_length == 3 {
+        0
+    } else if char_idx == 1 {
+        1
+    } else if char_idx == full_name_length - 2 {
+        2
+    } else {
+        3
     }
-    Ok(vec![0, 1, 1, 1, 1, 0])
 }
 
-fn _create_length_mask(full_name_length: &usize, char_idx: &usize) -> Vec<i32> {
+fn _create_length_mask(full_name_length: usize, char_idx: usize) -> [i32; 8] {
     let min_family = char_idx + 1;
     let min_family_idx = if min_family > 4 { 4 } else { min_family };
     let max_family = full_name_length - 1;
@@ -29,34 +24,32 @@ fn _create_length_mask(full_name_length: &usize, char_idx: &usize) -> Vec<i32> {
     let max_given = full_name_length - 1;
     let max_given_idx = if max_given > 4 { 4 } else { max_given };
 
-    let mut lc_family = vec![0, 0, 0, 0];
+    let mut mask = [0; 8];
     if min_family <= max_family {
-        for i in min_family_idx - 1..max_family_idx {
-            lc_family[i] = 1
+        for m in &mut mask[min_family_idx - 1..max_family_idx] {
+            *m = 1;
         }
     }
-    let mut lc_given = vec![0, 0, 0, 0];
     if min_given <= max_given {
-        for i in min_given_idx - 1..max_given_idx {
-            lc_given[i] = 1
+        for m in &mut mask[4 + min_given_idx - 1..4 + max_given_idx] {
+            *m = 1;
         }
     }
-    lc_family.append(&mut lc_given);
-    lc_family
+    mask
 }
 
-fn _calc_current_order_status(
-    piece_of_divided_name: &String,
-    idx_in_piece_of_divided_name: &usize,
+pub fn _calc_current_order_status(
+    piece_of_divided_name_length: usize,
+    idx_in_piece_of_divided_name: usize,
     is_family: bool,
 ) -> usize {
-    return if idx_in_piece_of_divided_name == &0 {
+    if idx_in_piece_of_divided_name == 0 {
         if is_family {
             0
         } else {
             3
         }
-    } else if idx_in_piece_of_divided_name == &(piece_of_divided_name.chars().count() - 1) {
+    } else if idx_in_piece_of_divided_name == piece_of_divided_name_length - 1 {
         if is_family {
             2
         } else {
@@ -66,11 +59,10 @@ fn _calc_current_order_status(
         1
     } else {
         4
-    };
+    }
 }
 
-fn _calc_current_length_status(piece_of_divided_name: &String, is_family: bool) -> usize {
-    let piece_of_divided_name_length = piece_of_divided_name.chars().count();
+pub fn _calc_current_length_status(piece_of_divided_name_length: usize, is_family: bool) -> usize {
     let length_for_get_status = if piece_of_divided_name_length <= 4 {
         piece_of_divided_name_length
     } else {
@@ -85,70 +77,55 @@ fn _calc_current_length_status(piece_of_divided_name: &String, is_family: bool)
 
 pub fn calc_order_score(
     kanji_statistics_repository: &kanji::KanjiStatisticsRepository,
-    piece_of_divided_name: &String,
+    piece_of_divided_name: &str,
     full_name_length: usize,
     start_index: usize,
 ) -> f64 {
     let is_family = start_index == 0;
+    let piece_of_divided_name_length = piece_of_divided_name.chars().count();
     let mut scores = 0.0;
-    for (idx_in_piece_of_divided_name, _kanji) in
-        piece_of_divided_name.chars().into_iter().enumerate()
-    {
+    for (idx_in_piece_of_divided_name, _kanji) in piece_of_divided_name.chars().enumerate() {
         let current_index = start_index + idx_in_piece_of_divided_name;
         if current_index == 0 || current_index == (full_name_length - 1) {
             continue;
         }
-        let mask = _create_order_mask(&full_name_length, &current_index).unwrap();
         let current_order_status_idx = _calc_current_order_status(
-            piece_of_divided_name,
-            &idx_in_piece_of_divided_name,
+            piece_of_divided_name_length,
+            idx_in_piece_of_divided_name,
             is_family,
         );
-        let _order_counts = &kanji_statistics_repository
-            .get(&_kanji.to_string())
-            .order_counts;
-        let mut masked_order_scores = vec![0, 0, 0, 0, 0, 0];
-        for i in 0..6 {
-            masked_order_scores[i] = &_order_counts[i] * mask[i];
-        }
-        let _sum: f64 = f64::from(masked_order_scores.iter().sum::<i32>());
-        if _sum == 0.0 {
-            continue;
-        }
-        let _score = f64::from(masked_order_scores[current_order_status_idx]) / _sum;
-        scores += _score;
+        let order_probabilities = kanji_statistics_repository
+            .order_probabilities(_kanji, _order_mask_idx(full_name_length, current_index));
+        scores += order_probabilities[current_order_status_idx];
     }
     scores
 }
 
 pub fn calc_length_score(
     kanji_statistics_repository: &kanji::KanjiStatisticsRepository,
-    piece_of_divided_name: &String,
+    piece_of_divided_name: &str,
     full_name_length: usize,
     start_index: usize,
 ) -> f64 {
     let is_family = start_index == 0;
+    let piece_of_divided_name_length = piece_of_divided_name.chars().count();
+    let current_length_status_idx =
+        _calc_current_length_status(piece_of_divided_name_length, is_family);
     let mut scores = 0.0;
-    for (idx_in_piece_of_divided_name, _kanji) in
-        piece_of_divided_name.chars().into_iter().enumerate()
-    {
+    for (idx_in_piece_of_divided_name, _kanji) in piece_of_divided_name.chars().enumerate() {
         let current_idx = start_index + idx_in_piece_of_divided_name;
-        let mask = _create_length_mask(&full_name_length, &current_idx);
-        let current_length_status_idx =
-            _calc_current_length_status(piece_of_divided_name, is_family);
-        let _length_counts = &kanji_statistics_repository
-            .get(&_kanji.to_string())
-            .length_counts;
-        let mut masked_length_scores = vec![0, 0, 0, 0, 0, 0, 0, 0];
-        for i in 0..8 {
-            masked_length_scores[i] = &_length_counts[i] * mask[i];
+        let mask = _create_length_mask(full_name_length, current_idx);
+        let _length_counts = kanji_statistics_repository.length_counts(_kanji);
+        let mut _sum = 0;
+        for (count, m) in _length_counts.iter().zip(&mask) {
+            _sum += count * m;
         }
-        let _sum: f64 = f64::from(masked_length_scores.iter().sum::<i32>());
-        if _sum == 0.0 {
+        if _sum == 0 {
             continue;
         }
-        let _score = f64::from(masked_length_scores[current_length_status_idx]) / _sum;
-        scores += _score;
+        let masked_length_score =
+            _length_counts[current_length_status_idx] * mask[current_length_status_idx];
+        scores += f64::from(masked_length_score) / f64::from(_sum);
     }
     scores
 }
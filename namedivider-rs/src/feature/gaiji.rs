@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+/// Maps gaiji in the Private Use Area to the standard chars they stand for.
+///
+/// A mapped char is scored as its standard equivalent, while the original code point
+/// is what appears in the divided name.
+pub struct GaijiMapping {
+    mapping: HashMap<char, String>,
+}
+
+pub fn is_private_use(c: char) -> bool {
+    matches!(
+        c,
+        '\u{E000}'..='\u{F8FF}' | '\u{F0000}'..='\u{FFFFD}' | '\u{100000}'..='\u{10FFFD}'
+    )
+}
+
+pub(crate) fn parse_chars(field: &str) -> Result<String, &'static str> {
+    if !field.starts_with("U+") && !field.starts_with("u+") {
+        return Ok(field.to_string());
+    }
+    field
+        .split_whitespace()
+        .map(|code_point| {
+            code_point
+                .get(2..)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32)
+                .ok_or("Invalid code point in gaiji mapping.")
+        })
+        .collect()
+}
+
+impl GaijiMapping {
+    /// Reads one mapping per line: the gaiji, a tab, and the standard char. Either side
+    /// may be written as is or as space-separated code points such as `U+845B U+E0100`,
+    /// so the standard side can carry an IVS selector. Empty lines and lines starting
+    /// with `#` are skipped.
+    pub fn from_text(contents: &str) -> Result<Self, &'static str> {
+        let mut mapping = HashMap::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (gaiji, standard) = line
+                .split_once('\t')
+                .ok_or("Gaiji mapping line needs a tab between the gaiji and the standard char.")?;
+            let gaiji = parse_chars(gaiji.trim())?;
+            let standard = parse_chars(standard.trim())?;
+            let mut gaiji_chars = gaiji.chars();
+            let gaiji = match (gaiji_chars.next(), gaiji_chars.next()) {
+                (Some(gaiji), None) => gaiji,
+                _ => return Err("Gaiji must be a single char."),
+            };
+            if !is_private_use(gaiji) {
+                return Err("Gaiji must be in the Private Use Area.");
+            }
+            if standard.is_empty() {
+                return Err("Standard char of a gaiji must not be empty.");
+            }
+            mapping.insert(gaiji, standard);
+        }
+        Ok(Self { mapping })
+    }
+
+    /// The standard chars for `gaiji`, possibly followed by a variation selector.
+    pub fn get(&self, gaiji: char) -> Option<&str> {
+        self.mapping.get(&gaiji).map(|standard| standard.as_str())
+    }
+
+    /// The char `c` is scored as: the base char of its mapping, or `c` itself.
+    pub fn map_char(&self, c: char) -> char {
+        self.get(c)
+            .and_then(|standard| standard.chars().next())
+            .unwrap_or(c)
+    }
+
+    pub fn len(&self) -> usize {
+        self.mapping.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mapping.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::gaiji::GaijiMapping;
+
+    #[test]
+    fn test_gaiji_mapping_from_text() {
+        let gaiji_mapping = GaijiMapping::from_text(
+            "# gaiji\tstandard\n\u{E000}\t葛\nU+E001\tU+845B U+E0100\n\nU+F0000\t髙\n",
+        )
+        .unwrap();
+        assert_eq!(gaiji_mapping.len(), 3);
+        assert_eq!(gaiji_mapping.get('\u{E000}'), Some("葛"));
+        assert_eq!(gaiji_mapping.get('\u{E001}'), Some("葛\u{E0100}"));
+        assert_eq!(gaiji_mapping.map_char('\u{E001}'), '葛');
+        assert_eq!(gaiji_mapping.map_char('\u{F0000}'), '髙');
+        assert_eq!(gaiji_mapping.map_char('田'), '田');
+
+        assert!(GaijiMapping::from_text("田\t由\n").is_err());
+        assert!(GaijiMapping::from_text("\u{E000} 葛\n").is_err());
+        assert!(GaijiMapping::from_text("U+ZZZZ\t葛\n").is_err());
+    }
+}
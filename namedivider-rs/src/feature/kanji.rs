@@ -1,9 +1,14 @@
 use crate::feature::assets::Asset;
+use crate::feature::compiled_assets::{CompiledAssets, CompiledKanjiTable};
+use crate::feature::functional::{_calc_current_length_status, _calc_current_order_status};
+use serde::de::Error;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasherDefault, Hasher};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KanjiStatistics {
     pub kanji: String,
     pub order_counts: Vec<i32>,
@@ -15,43 +20,327 @@ struct KanjiStatisticsVec {
     kanji_statistics_vec: Vec<KanjiStatistics>,
 }
 
+/// Every kanji needs 6 order counts and 8 length counts, as scoring assumes.
+pub fn parse_kanji_statistics(contents: &str) -> Result<Vec<KanjiStatistics>, serde_json::Error> {
+    let deserialized: KanjiStatisticsVec = serde_json::from_str(contents)?;
+    for kanji_statistics in &deserialized.kanji_statistics_vec {
+        if kanji_statistics.order_counts.len() != 6 || kanji_statistics.length_counts.len() != 8 {
+            return Err(serde_json::Error::custom(format!(
+                "{} needs 6 order counts and 8 length counts",
+                kanji_statistics.kanji
+            )));
+        }
+    }
+    Ok(deserialized.kanji_statistics_vec)
+}
+
+/// Writes statistics in the format of the embedded `kanji.json`, one kanji per line.
+pub fn to_kanji_json(kanji_statistics: &[KanjiStatistics]) -> String {
+    let lines: Vec<String> = kanji_statistics
+        .iter()
+        .map(|kanji_statistics| serde_json::to_string(kanji_statistics).unwrap())
+        .collect();
+    format!("{{\"kanji_statistics_vec\":\n[{}]}}\n", lines.join(",\n "))
+}
+
+/// Counts the order and length status of every char of divided names, as the
+/// embedded `kanji.json` does.
+pub struct KanjiStatisticsBuilder {
+    counts: BTreeMap<char, ([i32; 6], [i32; 8])>,
+}
+
+impl Default for KanjiStatisticsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KanjiStatisticsBuilder {
+    pub fn new() -> Self {
+        Self {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, family: &str, given: &str) {
+        for (piece_of_divided_name, is_family) in [(family, true), (given, false)] {
+            let piece_of_divided_name_length = piece_of_divided_name.chars().count();
+            let length_status =
+                _calc_current_length_status(piece_of_divided_name_length, is_family);
+            for (idx, kanji) in piece_of_divided_name.chars().enumerate() {
+                let order_status =
+                    _calc_current_order_status(piece_of_divided_name_length, idx, is_family);
+                let (order_counts, length_counts) =
+                    self.counts.entry(kanji).or_insert(([0; 6], [0; 8]));
+                order_counts[order_status] += 1;
+                length_counts[length_status] += 1;
+            }
+        }
+    }
+
+    /// Drops the chars seen fewer than `min_count` times. The statistics are in char order.
+    pub fn build(self, min_count: u32) -> Vec<KanjiStatistics> {
+        self.counts
+            .into_iter()
+            .filter(|(_, (order_counts, _))| {
+                order_counts.iter().map(|&count| count as u32).sum::<u32>() >= min_count
+            })
+            .map(|(kanji, (order_counts, length_counts))| KanjiStatistics {
+                kanji: kanji.to_string(),
+                order_counts: order_counts.to_vec(),
+                length_counts: length_counts.to_vec(),
+            })
+            .collect()
+    }
+}
+
+/// Masks applied to `order_counts` by the position of a char in the full name,
+/// excluding the first and the last char which have no order score.
+pub(crate) const ORDER_MASKS: [[i32; 6]; 4] = [
+    // Full name of 3 chars
+    [0, 0, 1, 1, 0, 0],
+    // Second char
+    [0, 1, 1, 1, 0, 0],
+    // Second to last char
+    [0, 0, 1, 1, 1, 0],
+    // Any other char
+    [0, 1, 1, 1, 1, 0],
+];
+
+/// `KanjiStatistics` laid out for scoring: fixed-size counts and, for each order mask,
+/// the probability of each order status.
+struct KanjiScoreTable {
+    order_probabilities: [[f64; 6]; 4],
+    length_counts: [i32; 8],
+}
+
+impl KanjiScoreTable {
+    fn new(kanji_statistics: &KanjiStatistics) -> Self {
+        let mut order_probabilities = [[0.0; 6]; 4];
+        for (mask_idx, probabilities) in order_probabilities.iter_mut().enumerate() {
+            *probabilities = order_probabilities_of(&kanji_statistics.order_counts, mask_idx);
+        }
+        let mut length_counts = [0; 8];
+        length_counts.copy_from_slice(&kanji_statistics.length_counts[..8]);
+        Self {
+            order_probabilities,
+            length_counts,
+        }
+    }
+}
+
+/// The probability of each order status among those `ORDER_MASKS[mask_idx]` allows.
+fn order_probabilities_of(order_counts: &[i32], mask_idx: usize) -> [f64; 6] {
+    let mut masked_order_counts = [0; 6];
+    for ((masked_count, count), m) in masked_order_counts
+        .iter_mut()
+        .zip(order_counts)
+        .zip(&ORDER_MASKS[mask_idx])
+    {
+        *masked_count = count * m;
+    }
+    let mut probabilities = [0.0; 6];
+    let _sum: f64 = f64::from(masked_order_counts.iter().sum::<i32>());
+    if _sum == 0.0 {
+        return probabilities;
+    }
+    for (probability, masked_count) in probabilities.iter_mut().zip(&masked_order_counts) {
+        *probability = f64::from(*masked_count) / _sum;
+    }
+    probabilities
+}
+
+/// Multiplicative hasher for `char` keys; SipHash is needlessly slow for a 4-byte key.
+#[derive(Default)]
+pub struct CharHasher {
+    hash: u64,
+}
+
+impl Hasher for CharHasher {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_u64(*byte as u64);
+        }
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ i).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+}
+
+pub type CharMap<V> = HashMap<char, V, BuildHasherDefault<CharHasher>>;
+
+/// Where the statistics of a `KanjiStatisticsRepository` are looked up.
+enum KanjiTable {
+    /// Parsed from `kanji.json`, with the score table of every kanji computed up front.
+    Parsed(CharMap<(KanjiStatistics, KanjiScoreTable)>),
+    /// Served from the records of a compiled bundle, scored as they are looked up.
+    Compiled(CompiledKanjiTable),
+}
+
 pub struct KanjiStatisticsRepository {
-    kanji_dict: HashMap<String, KanjiStatistics>,
-    default_kanji: KanjiStatistics,
+    kanji_table: KanjiTable,
+    default_kanji: (KanjiStatistics, KanjiScoreTable),
 }
 
 impl KanjiStatisticsRepository {
     pub fn new() -> Self {
         let contents = Asset::get("kanji.json").unwrap().data.as_ref().to_owned();
         let contents_str = std::str::from_utf8(&contents).unwrap();
-        let deserialized: KanjiStatisticsVec = serde_json::from_str(contents_str).unwrap();
-        let mut kanji_dict = HashMap::new();
-        for _d in deserialized.kanji_statistics_vec {
-            kanji_dict.insert(
-                _d.kanji.clone(),
-                KanjiStatistics {
-                    kanji: _d.kanji.clone(),
-                    order_counts: _d.order_counts.clone(),
-                    length_counts: _d.length_counts.clone(),
-                },
-            );
+        Self::from_json(contents_str).unwrap()
+    }
+
+    /// Loads statistics in the format of the embedded `kanji.json`.
+    pub fn from_json(contents: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self::from_statistics(parse_kanji_statistics(contents)?))
+    }
+
+    /// Looks the kanji up in the records of the bundle, without decoding them up front.
+    pub fn from_compiled(compiled_assets: &CompiledAssets) -> Self {
+        Self::with_kanji_table(KanjiTable::Compiled(compiled_assets.kanji_table()))
+    }
+
+    fn from_statistics(kanji_statistics: impl IntoIterator<Item = KanjiStatistics>) -> Self {
+        let mut kanji_dict = CharMap::default();
+        for _d in kanji_statistics {
+            let kanji = match _d.kanji.chars().next() {
+                Some(kanji) => kanji,
+                None => continue,
+            };
+            let score_table = KanjiScoreTable::new(&_d);
+            kanji_dict.insert(kanji, (_d, score_table));
         }
+        Self::with_kanji_table(KanjiTable::Parsed(kanji_dict))
+    }
 
+    fn with_kanji_table(kanji_table: KanjiTable) -> Self {
+        let default_kanji = KanjiStatistics {
+            kanji: "default".to_string(),
+            order_counts: vec![0, 0, 0, 0, 0, 0],
+            length_counts: vec![0, 0, 0, 0, 0, 0, 0, 0],
+        };
+        let default_score_table = KanjiScoreTable::new(&default_kanji);
         Self {
-            kanji_dict,
-            default_kanji: KanjiStatistics {
-                kanji: "default".to_string(),
-                order_counts: vec![0, 0, 0, 0, 0, 0],
-                length_counts: vec![0, 0, 0, 0, 0, 0, 0, 0],
+            kanji_table,
+            default_kanji: (default_kanji, default_score_table),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Cow<'_, KanjiStatistics> {
+        let mut chars = key.chars();
+        let kanji = match (chars.next(), chars.next()) {
+            (Some(kanji), None) => kanji,
+            _ => return Cow::Borrowed(&self.default_kanji.0),
+        };
+        match &self.kanji_table {
+            KanjiTable::Parsed(kanji_dict) => Cow::Borrowed(
+                kanji_dict
+                    .get(&kanji)
+                    .map_or(&self.default_kanji.0, |(kanji_statistics, _)| {
+                        kanji_statistics
+                    }),
+            ),
+            KanjiTable::Compiled(kanji_table) => match kanji_table.get(kanji) {
+                Some((order_counts, length_counts)) => Cow::Owned(KanjiStatistics {
+                    kanji: kanji.to_string(),
+                    order_counts: order_counts.to_vec(),
+                    length_counts: length_counts.to_vec(),
+                }),
+                None => Cow::Borrowed(&self.default_kanji.0),
             },
         }
     }
 
-    pub fn get(&self, key: &String) -> &KanjiStatistics {
-        let kanji = self.kanji_dict.get(key);
-        if kanji.is_none() {
-            return &self.default_kanji;
+    /// Whether `kanji` has statistics of its own rather than those of `default_kanji`,
+    /// which are all zero.
+    pub fn contains(&self, kanji: char) -> bool {
+        match &self.kanji_table {
+            KanjiTable::Parsed(kanji_dict) => kanji_dict.contains_key(&kanji),
+            KanjiTable::Compiled(kanji_table) => kanji_table.get(kanji).is_some(),
+        }
+    }
+
+    /// The probability of each order status of `kanji` under `ORDER_MASKS[mask_idx]`.
+    pub fn order_probabilities(&self, kanji: char, mask_idx: usize) -> [f64; 6] {
+        match &self.kanji_table {
+            KanjiTable::Parsed(kanji_dict) => {
+                let (_, score_table) = kanji_dict.get(&kanji).unwrap_or(&self.default_kanji);
+                score_table.order_probabilities[mask_idx]
+            }
+            KanjiTable::Compiled(kanji_table) => match kanji_table.get(kanji) {
+                Some((order_counts, _)) => order_probabilities_of(&order_counts, mask_idx),
+                None => self.default_kanji.1.order_probabilities[mask_idx],
+            },
         }
-        kanji.unwrap()
+    }
+
+    pub fn length_counts(&self, kanji: char) -> [i32; 8] {
+        match &self.kanji_table {
+            KanjiTable::Parsed(kanji_dict) => {
+                let (_, score_table) = kanji_dict.get(&kanji).unwrap_or(&self.default_kanji);
+                score_table.length_counts
+            }
+            KanjiTable::Compiled(kanji_table) => match kanji_table.get(kanji) {
+                Some((_, length_counts)) => length_counts,
+                None => self.default_kanji.1.length_counts,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::kanji::{
+        parse_kanji_statistics, to_kanji_json, KanjiStatisticsBuilder, KanjiStatisticsRepository,
+    };
+
+    #[test]
+    fn test_kanji_statistics_builder() {
+        let mut builder = KanjiStatisticsBuilder::new();
+        builder.add("田中", "太郎");
+        builder.add("中田", "一太郎");
+        builder.add("原", "敬");
+        let kanji_statistics = builder.build(2);
+        let kanji: Vec<&str> = kanji_statistics.iter().map(|k| k.kanji.as_str()).collect();
+        assert_eq!(kanji, vec!["中", "太", "田", "郎"]);
+        // 中 ends a 2-char family once and starts one once.
+        assert_eq!(kanji_statistics[0].order_counts, vec![1, 0, 1, 0, 0, 0]);
+        assert_eq!(
+            kanji_statistics[0].length_counts,
+            vec![0, 2, 0, 0, 0, 0, 0, 0]
+        );
+        // 太 starts a 2-char given name and is in the middle of a 3-char one.
+        assert_eq!(kanji_statistics[1].order_counts, vec![0, 0, 0, 1, 1, 0]);
+        assert_eq!(
+            kanji_statistics[1].length_counts,
+            vec![0, 0, 0, 0, 0, 1, 1, 0]
+        );
+
+        let contents = to_kanji_json(&kanji_statistics);
+        let restored = parse_kanji_statistics(&contents).unwrap();
+        assert_eq!(restored.len(), 4);
+        assert_eq!(restored[3].order_counts, kanji_statistics[3].order_counts);
+        let repository = KanjiStatisticsRepository::from_json(&contents).unwrap();
+        assert_eq!(
+            repository.get("郎").length_counts,
+            vec![0, 0, 0, 0, 0, 1, 1, 0]
+        );
+        assert_eq!(repository.get("敬").kanji, "default");
+        assert!(repository.contains('郎'));
+        assert!(!repository.contains('敬'));
+    }
+
+    #[test]
+    fn test_from_json_with_short_counts() {
+        let contents = r#"{"kanji_statistics_vec": [{"kanji": "田", "order_counts": [1, 0, 0, 0, 0, 0], "length_counts": [0, 1]}]}"#;
+        assert!(KanjiStatisticsRepository::from_json(contents).is_err());
     }
 }
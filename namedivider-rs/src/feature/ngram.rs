@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"NDNG";
+const FORMAT_VERSION: u8 = 2;
+pub const NGRAM_SIZES: [usize; 2] = [2, 3];
+
+/// Where the family/given boundary falls inside one character n-gram.
+///
+/// `boundary_counts[i]` counts the times the boundary came right after the (i + 1)-th
+/// character of the n-gram, and `family_count` and `given_count` the times the n-gram
+/// sat inside the family or the given name without crossing the boundary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NgramStatistics {
+    pub boundary_counts: Vec<u32>,
+    pub family_count: u32,
+    pub given_count: u32,
+}
+
+impl NgramStatistics {
+    fn new(ngram_size: usize) -> Self {
+        Self {
+            boundary_counts: vec![0; ngram_size - 1],
+            family_count: 0,
+            given_count: 0,
+        }
+    }
+
+    pub fn total_count(&self) -> u32 {
+        self.boundary_counts.iter().sum::<u32>() + self.family_count + self.given_count
+    }
+}
+
+pub struct NgramStatisticsRepository {
+    ngram_dict: HashMap<String, NgramStatistics>,
+}
+
+impl NgramStatisticsRepository {
+    pub fn get(&self, ngram: &str) -> Option<&NgramStatistics> {
+        self.ngram_dict.get(ngram)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ngram_dict.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ngram_dict.is_empty()
+    }
+
+    /// Serializes the statistics as `NDNG`, version byte, entry count and then the entries
+    /// sorted by n-gram. Each entry is the UTF-8 length, the n-gram and its counts,
+    /// with every integer written as an unsigned LEB128 varint.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut ngrams: Vec<&String> = self.ngram_dict.keys().collect();
+        ngrams.sort();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        write_varint(&mut bytes, ngrams.len() as u32);
+        for ngram in ngrams {
+            let statistics = &self.ngram_dict[ngram];
+            write_varint(&mut bytes, ngram.len() as u32);
+            bytes.extend_from_slice(ngram.as_bytes());
+            for count in &statistics.boundary_counts {
+                write_varint(&mut bytes, *count);
+            }
+            write_varint(&mut bytes, statistics.family_count);
+            write_varint(&mut bytes, statistics.given_count);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 5 || &bytes[..4] != MAGIC {
+            return Err("Not an n-gram statistics file.");
+        }
+        if bytes[4] != FORMAT_VERSION {
+            return Err("Unsupported n-gram statistics format version.");
+        }
+        let mut pos = 5;
+        let num_ngrams = read_varint(bytes, &mut pos)?;
+        let mut ngram_dict = HashMap::new();
+        for _ in 0..num_ngrams {
+            let ngram_length = read_varint(bytes, &mut pos)? as usize;
+            let ngram_bytes = bytes
+                .get(pos..pos + ngram_length)
+                .ok_or("Unexpected end of n-gram statistics.")?;
+            let ngram = std::str::from_utf8(ngram_bytes)
+                .map_err(|_| "N-gram is not valid UTF-8.")?
+                .to_string();
+            pos += ngram_length;
+            let ngram_size = ngram.chars().count();
+            if ngram_size < 2 {
+                return Err("N-gram must have at least 2 chars.");
+            }
+            let mut statistics = NgramStatistics::new(ngram_size);
+            for count in statistics.boundary_counts.iter_mut() {
+                *count = read_varint(bytes, &mut pos)?;
+            }
+            statistics.family_count = read_varint(bytes, &mut pos)?;
+            statistics.given_count = read_varint(bytes, &mut pos)?;
+            ngram_dict.insert(ngram, statistics);
+        }
+        if pos != bytes.len() {
+            return Err("Trailing bytes after n-gram statistics.");
+        }
+        Ok(Self { ngram_dict })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u32, &'static str> {
+    let mut value: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes
+            .get(*pos)
+            .ok_or("Unexpected end of n-gram statistics.")?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err("Varint is too long.")
+}
+
+pub struct NgramStatisticsBuilder {
+    ngram_dict: HashMap<String, NgramStatistics>,
+}
+
+impl Default for NgramStatisticsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NgramStatisticsBuilder {
+    pub fn new() -> Self {
+        Self {
+            ngram_dict: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, family: &str, given: &str) {
+        let chars: Vec<char> = family.chars().chain(given.chars()).collect();
+        let boundary = family.chars().count();
+        for ngram_size in NGRAM_SIZES {
+            if chars.len() < ngram_size {
+                continue;
+            }
+            for start in 0..=chars.len() - ngram_size {
+                let ngram: String = chars[start..start + ngram_size].iter().collect();
+                let statistics = self
+                    .ngram_dict
+                    .entry(ngram)
+                    .or_insert_with(|| NgramStatistics::new(ngram_size));
+                if start + ngram_size <= boundary {
+                    statistics.family_count += 1;
+                } else if start >= boundary {
+                    statistics.given_count += 1;
+                } else {
+                    statistics.boundary_counts[boundary - start - 1] += 1;
+                }
+            }
+        }
+    }
+
+    /// Drops the n-grams seen fewer than `min_count` times.
+    pub fn build(self, min_count: u32) -> NgramStatisticsRepository {
+        let ngram_dict = self
+            .ngram_dict
+            .into_iter()
+            .filter(|(_, statistics)| statistics.total_count() >= min_count)
+            .collect();
+        NgramStatisticsRepository { ngram_dict }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::ngram::{
+        NgramStatistics, NgramStatisticsBuilder, NgramStatisticsRepository,
+    };
+
+    #[test]
+    fn test_ngram_statistics_builder() {
+        let mut builder = NgramStatisticsBuilder::new();
+        builder.add("田中", "太郎");
+        builder.add("中田", "太");
+        let repository = builder.build(1);
+        assert_eq!(
+            repository.get("中太"),
+            Some(&NgramStatistics {
+                boundary_counts: vec![1],
+                family_count: 0,
+                given_count: 0,
+            })
+        );
+        assert_eq!(repository.get("田太").unwrap().boundary_counts, vec![1]);
+        assert_eq!(repository.get("田中").unwrap().family_count, 1);
+        assert_eq!(repository.get("田中").unwrap().given_count, 0);
+        assert_eq!(repository.get("太郎").unwrap().given_count, 1);
+        assert_eq!(
+            repository.get("田中太").unwrap().boundary_counts,
+            vec![0, 1]
+        );
+        assert_eq!(
+            repository.get("中太郎").unwrap().boundary_counts,
+            vec![1, 0]
+        );
+    }
+
+    #[test]
+    fn test_ngram_statistics_bytes_round_trip() {
+        let mut builder = NgramStatisticsBuilder::new();
+        for _ in 0..200 {
+            builder.add("佐藤", "花子");
+        }
+        builder.add("髙橋", "一");
+        let repository = builder.build(2);
+        assert_eq!(repository.get("髙橋"), None);
+        let restored = NgramStatisticsRepository::from_bytes(&repository.to_bytes()).unwrap();
+        assert_eq!(restored.len(), repository.len());
+        assert_eq!(restored.get("藤花").unwrap().boundary_counts, vec![200]);
+        assert_eq!(restored.get("佐藤").unwrap().family_count, 200);
+        assert_eq!(restored.get("花子").unwrap().given_count, 200);
+        assert!(NgramStatisticsRepository::from_bytes(b"NDKJ\x01").is_err());
+        let mut bytes = repository.to_bytes();
+        bytes.push(0);
+        assert!(NgramStatisticsRepository::from_bytes(&bytes).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+use crate::feature::family_name::FamilyNameRepository;
+use crate::feature::kanji::KanjiStatisticsRepository;
+use std::sync::{Arc, OnceLock};
+
+/// The embedded kanji statistics, loaded on first use and shared by every divider in the process.
+pub fn kanji_statistics_repository() -> Arc<KanjiStatisticsRepository> {
+    static REPOSITORY: OnceLock<Arc<KanjiStatisticsRepository>> = OnceLock::new();
+    REPOSITORY
+        .get_or_init(|| Arc::new(KanjiStatisticsRepository::new()))
+        .clone()
+}
+
+/// The embedded family names, loaded on first use and shared by every divider in the process.
+pub fn family_name_repository() -> Arc<FamilyNameRepository> {
+    static REPOSITORY: OnceLock<Arc<FamilyNameRepository>> = OnceLock::new();
+    REPOSITORY
+        .get_or_init(|| Arc::new(FamilyNameRepository::new()))
+        .clone()
+}
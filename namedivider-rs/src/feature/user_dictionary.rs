@@ -0,0 +1,82 @@
+use crate::corpus::parse_divided_name;
+use crate::divider::name_divider_base::is_unit_extender;
+use std::collections::HashMap;
+
+/// Splits decided by hand, which take precedence over the rules and the score calculator.
+///
+/// Names are looked up as written, before normalization.
+pub struct UserDictionary {
+    /// Length of the family name in units, by the undivided name.
+    family_lengths: HashMap<String, usize>,
+}
+
+impl Default for UserDictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserDictionary {
+    pub fn new() -> Self {
+        Self {
+            family_lengths: HashMap::new(),
+        }
+    }
+
+    /// Reads one divided name per line, the family and the given name separated by a
+    /// space as in a divided name corpus. Empty lines and lines starting with `#` are
+    /// skipped, and a later line for the same name replaces an earlier one.
+    pub fn from_text(contents: &str) -> Result<Self, &'static str> {
+        let mut user_dictionary = Self::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let gold_name = parse_divided_name(line, " ").ok_or(
+                "User dictionary line needs a space between the family and the given name.",
+            )?;
+            user_dictionary.insert(&gold_name.family, &gold_name.given);
+        }
+        Ok(user_dictionary)
+    }
+
+    pub fn insert(&mut self, family: &str, given: &str) {
+        let family_length = family.chars().filter(|c| !is_unit_extender(*c)).count();
+        self.family_lengths
+            .insert(family.to_string() + given, family_length);
+    }
+
+    /// Length of the family name of `undivided_name` in units, when the name is in the
+    /// dictionary.
+    pub fn get(&self, undivided_name: &str) -> Option<usize> {
+        self.family_lengths.get(undivided_name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.family_lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.family_lengths.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::feature::user_dictionary::UserDictionary;
+
+    #[test]
+    fn test_user_dictionary_from_text() {
+        let user_dictionary = UserDictionary::from_text(
+            "# family given\n小泉進 次郎\n\n葛\u{E0100}城 一郎\n小泉 進次郎\n",
+        )
+        .unwrap();
+        assert_eq!(user_dictionary.len(), 2);
+        assert_eq!(user_dictionary.get("小泉進次郎"), Some(2));
+        assert_eq!(user_dictionary.get("葛\u{E0100}城一郎"), Some(2));
+        assert_eq!(user_dictionary.get("菅義偉"), None);
+
+        assert!(UserDictionary::from_text("菅義偉\n").is_err());
+    }
+}
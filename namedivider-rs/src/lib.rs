@@ -1,2 +1,8 @@
+pub mod corpus;
 pub mod divider;
+pub mod encodability;
+pub mod evaluation;
 pub mod feature;
+pub mod parity;
+pub mod pipeline;
+pub mod text_encoding;
@@ -1,117 +1,3402 @@
-use clap::{Parser, Subcommand};
-use namedivider_rs::divider::basic_name_divider::get_basic_name_divider;
-use namedivider_rs::divider::divided_name::DividedName;
-use namedivider_rs::divider::gbdt_name_divider::get_gbdt_name_divider;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use namedivider_rs::corpus::{parse_divided_name, parse_divided_name_columns, GoldName};
+use namedivider_rs::divider::basic_name_divider::{
+    get_basic_name_divider_with_repository, BasicNameDivider,
+};
+use namedivider_rs::divider::basic_score_weights::{fit_basic_score_weights, BasicScoreWeights};
+use namedivider_rs::divider::divided_name::{DividedName, Span};
+use namedivider_rs::divider::gbdt_name_divider::{
+    get_gbdt_name_divider_from_compiled, get_gbdt_name_divider_with_model, GBDTNameDivider,
+};
+use namedivider_rs::divider::gbdt_trainer::{
+    family_ranking_feature_rows, split_validation_names, train_gbdt_model, FeatureRow,
+    GBDTTrainerConfig,
+};
+use namedivider_rs::divider::logistic_name_divider::get_logistic_name_divider;
+use namedivider_rs::divider::logistic_score_calculator::LogisticModel;
+use namedivider_rs::divider::logistic_trainer::{train_logistic_model, LogisticTrainerConfig};
 use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::name_divider_base::{NameDividerBase, UndividedNameHolder};
+use namedivider_rs::divider::ngram_name_divider::get_ngram_name_divider;
+use namedivider_rs::divider::provenance::{Provenance, EMBEDDED_GBDT_MODEL_VERSION};
+use namedivider_rs::divider::two_char_name_divider::TwoCharNameDivider;
+use namedivider_rs::encodability::{check_encodability, EncodingTarget, VariantTable};
+use namedivider_rs::evaluation::{evaluate_name, AccuracyReport, AccuracyReportBuilder};
+use namedivider_rs::feature::assets::Asset;
+use namedivider_rs::feature::compiled_assets::{
+    compile_assets, map_compiled_assets, CompiledAssets,
+};
+use namedivider_rs::feature::extractor::{
+    FamilyRankingFeatureExtractor, FAMILY_RANKING_FEATURE_NAMES,
+};
+use namedivider_rs::feature::family_name::FamilyNameRepository;
+use namedivider_rs::feature::gaiji::{is_private_use, GaijiMapping};
+use namedivider_rs::feature::kanji::{
+    parse_kanji_statistics, to_kanji_json, KanjiStatisticsBuilder, KanjiStatisticsRepository,
+};
+use namedivider_rs::feature::ngram::{NgramStatisticsBuilder, NgramStatisticsRepository};
+use namedivider_rs::feature::shared_assets;
+use namedivider_rs::feature::user_dictionary::UserDictionary;
+use namedivider_rs::parity::{parse_reference_division, ParityReport, ParityReportBuilder};
+use namedivider_rs::pipeline::{process_in_order, LineBatches};
+use namedivider_rs::text_encoding::{
+    byte_order_mark, decode_text, encode_text, InvalidTextPolicy, TextEncoding,
+};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufWriter, Read, Write};
 use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-fn divide_name(divider: &Box<dyn NameDivider>, undivided_name: &String) -> DividedName {
-    divider.divide_name(undivided_name)
+/// Names read per batch handed to a worker in the `file` subcommand.
+const BATCH_SIZE: usize = 1024;
+
+/// Exit code of bad arguments or options, the same as clap's own usage errors.
+const EXIT_USAGE: i32 = 2;
+/// Exit code of a file that couldn't be read, parsed or written.
+const EXIT_IO: i32 = 3;
+/// Exit code of a run that wrote what it could but had names it couldn't divide.
+const EXIT_DIVIDE: i32 = 4;
+
+/// An error that ends a subcommand, reported on stderr without a backtrace.
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Io(String),
+    Divide(String),
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Io(_) => EXIT_IO,
+            CliError::Divide(_) => EXIT_DIVIDE,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) | CliError::Io(message) | CliError::Divide(message) => {
+                write!(f, "{}", message)
+            }
+        }
+    }
+}
+
+/// The message for a name the divider refused with `why`.
+fn divide_error(undivided_name: &str, why: &str) -> String {
+    format!("couldn't divide {:?}: {}", undivided_name, why)
+}
+
+/// The error of a run that skipped `num_failed` names it couldn't divide.
+fn check_num_failed(num_failed: usize) -> Result<(), CliError> {
+    if num_failed == 0 {
+        Ok(())
+    } else {
+        Err(CliError::Divide(format!(
+            "some names couldn't be divided ({})",
+            num_failed
+        )))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum)]
+enum Mode {
+    /// Kanji statistics of the order and length of each char
+    Basic,
+    /// LightGBM on the kanji statistics and the family name ranks
+    Gbdt,
+    /// Logistic regression written by `train-logistic`; needs --model
+    Logistic,
+    /// Character n-gram statistics written by `build-ngram`; needs --model
+    Ngram,
+    /// Takes the first 2 chars as the family name, as a baseline
+    #[value(name = "two_char", alias = "two-char")]
+    TwoChar,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_possible_value().unwrap().get_name())
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<Mode, String> {
+    <Mode as ValueEnum>::from_str(mode, true).map_err(|_| {
+        let modes: Vec<String> = Mode::value_variants()
+            .iter()
+            .map(|mode| mode.to_string())
+            .collect();
+        format!("unknown mode: {} (one of {})", mode, modes.join(", "))
+    })
+}
+
+fn create_divider(
+    mode: Mode,
+    options: &DividerOptions,
+) -> Result<Box<dyn NameDivider + Send + Sync>, CliError> {
+    if mode == Mode::Gbdt && options.gbdt_model.is_some() && options.assets.is_some() {
+        return Err(CliError::Usage(
+            "use either --gbdt-model or --assets".to_string(),
+        ));
+    }
+    let compiled_assets = match &options.assets {
+        Some(assets_path) => Some(load_compiled_assets(assets_path)?),
+        None => None,
+    };
+    let gaiji_mapping = load_gaiji_mapping(options)?;
+    let user_dictionary = match &options.user_dictionary {
+        Some(user_dictionary_path) => Some(Arc::new(
+            UserDictionary::from_text(&read_file(user_dictionary_path)?).map_err(|why| {
+                CliError::Io(format!("couldn't parse {}: {}", user_dictionary_path, why))
+            })?,
+        )),
+        None => None,
+    };
+    let separator = options.separator.clone();
+    let normalize_name = !options.no_normalize;
+    let divider: Box<dyn NameDivider + Send + Sync> = match mode {
+        Mode::Basic => {
+            let mut basic_divider = create_basic_divider(options, compiled_assets.as_ref())?;
+            basic_divider.name_divider_base.gaiji_mapping = gaiji_mapping;
+            basic_divider.name_divider_base.user_dictionary = user_dictionary;
+            Box::new(basic_divider)
+        }
+        Mode::Logistic => {
+            let model_path = options.model.as_ref().ok_or_else(|| {
+                CliError::Usage(
+                    "the logistic mode needs --model, written by `train-logistic`".to_string(),
+                )
+            })?;
+            let model = LogisticModel::from_json(&read_file(model_path)?)
+                .map_err(|why| CliError::Io(format!("couldn't parse {}: {}", model_path, why)))?;
+            let mut logistic_divider = get_logistic_name_divider(separator, normalize_name, model);
+            logistic_divider.name_divider_base.gaiji_mapping = gaiji_mapping;
+            logistic_divider.name_divider_base.user_dictionary = user_dictionary;
+            Box::new(logistic_divider)
+        }
+        Mode::Ngram => {
+            let model_path = options.model.as_ref().ok_or_else(|| {
+                CliError::Usage(
+                    "the ngram mode needs --model, written by `build-ngram`".to_string(),
+                )
+            })?;
+            let model_bytes = std::fs::read(model_path)
+                .map_err(|why| CliError::Io(format!("couldn't read {}: {}", model_path, why)))?;
+            let ngram_statistics_repository = NgramStatisticsRepository::from_bytes(&model_bytes)
+                .map_err(|why| {
+                CliError::Io(format!("couldn't parse {}: {}", model_path, why))
+            })?;
+            let mut ngram_divider =
+                get_ngram_name_divider(separator, normalize_name, ngram_statistics_repository);
+            ngram_divider.name_divider_base.gaiji_mapping = gaiji_mapping;
+            ngram_divider.name_divider_base.user_dictionary = user_dictionary;
+            Box::new(ngram_divider)
+        }
+        Mode::Gbdt => {
+            let mut gbdt_divider = match (&options.gbdt_model, &compiled_assets) {
+                (Some(model_path), _) => get_gbdt_name_divider_with_model(
+                    separator,
+                    normalize_name,
+                    read_file(model_path)?,
+                    &model_version_from_path(model_path),
+                )
+                .map_err(|why| CliError::Io(format!("couldn't use {}: {}", model_path, why)))?,
+                (None, Some(compiled_assets)) => {
+                    get_gbdt_name_divider_from_compiled(separator, normalize_name, compiled_assets)
+                        .map_err(|why| {
+                            CliError::Io(format!(
+                                "couldn't use {}: {}",
+                                options.assets.as_ref().unwrap(),
+                                why
+                            ))
+                        })?
+                }
+                (None, None) => GBDTNameDivider::new(separator, normalize_name),
+            };
+            gbdt_divider.name_divider_base.gaiji_mapping = gaiji_mapping;
+            gbdt_divider.name_divider_base.user_dictionary = user_dictionary;
+            Box::new(gbdt_divider)
+        }
+        Mode::TwoChar => {
+            let mut two_char_divider = TwoCharNameDivider::new(separator, normalize_name);
+            two_char_divider.name_divider_base.gaiji_mapping = gaiji_mapping;
+            two_char_divider.name_divider_base.user_dictionary = user_dictionary;
+            Box::new(two_char_divider)
+        }
+    };
+    Ok(divider)
+}
+
+/// The divider of the basic mode, without the gaiji mapping and the user dictionary.
+fn create_basic_divider(
+    options: &DividerOptions,
+    compiled_assets: Option<&CompiledAssets>,
+) -> Result<BasicNameDivider, CliError> {
+    let weights = match &options.weights {
+        Some(weights_path) => BasicScoreWeights::from_json(&read_file(weights_path)?)
+            .map_err(|why| CliError::Io(format!("couldn't parse {}: {}", weights_path, why)))?,
+        None => BasicScoreWeights::default(),
+    };
+    let repo = match compiled_assets {
+        Some(compiled_assets) => {
+            Arc::new(KanjiStatisticsRepository::from_compiled(compiled_assets))
+        }
+        None => shared_assets::kanji_statistics_repository(),
+    };
+    Ok(get_basic_name_divider_with_repository(
+        options.separator.clone(),
+        !options.no_normalize,
+        options.only_order_score_when_4,
+        weights,
+        repo,
+    ))
+}
+
+fn load_gaiji_mapping(options: &DividerOptions) -> Result<Option<Arc<GaijiMapping>>, CliError> {
+    match &options.gaiji {
+        Some(gaiji_path) => Ok(Some(Arc::new(
+            GaijiMapping::from_text(&read_file(gaiji_path)?)
+                .map_err(|why| CliError::Io(format!("couldn't parse {}: {}", gaiji_path, why)))?,
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// The version reported for a model read from `model_path`: its file name without the extension.
+fn model_version_from_path(model_path: &String) -> String {
+    Path::new(model_path)
+        .file_stem()
+        .map_or(model_path.clone(), |stem| {
+            stem.to_string_lossy().to_string()
+        })
+}
+
+fn read_file(file_path: &String) -> Result<String, CliError> {
+    let path = Path::new(file_path);
+    let display = path.display();
+    let mut file = match File::open(file_path) {
+        Err(why) => return Err(CliError::Io(format!("couldn't open {}: {}", display, why))),
+        Ok(file) => file,
+    };
+    let mut s = String::new();
+    match file.read_to_string(&mut s) {
+        Err(why) => Err(CliError::Io(format!("couldn't read {}: {}", display, why))),
+        Ok(_) => Ok(s),
+    }
+}
+
+fn read_names_file(
+    file_path: &String,
+    text_encoding_options: &TextEncodingOptions,
+) -> Result<String, CliError> {
+    let bytes = std::fs::read(file_path)
+        .map_err(|why| CliError::Io(format!("couldn't read {}: {}", file_path, why)))?;
+    decode_text(
+        &bytes,
+        text_encoding_options.input_encoding,
+        text_encoding_options.encoding_errors,
+    )
+    .map_err(|why| CliError::Io(format!("couldn't decode {}: {}", file_path, why)))
+}
+
+/// Writes lines to stdout in the output encoding.
+///
+/// The first error is kept and nothing is written after it, so that writing from the
+/// callbacks of `process_in_order` needs no error handling; `finish` returns it.
+struct TextOutput {
+    stdout: BufWriter<std::io::StdoutLock<'static>>,
+    encoding: TextEncoding,
+    policy: InvalidTextPolicy,
+    num_lines: usize,
+    error: Option<CliError>,
+}
+
+impl TextOutput {
+    fn new(text_encoding_options: &TextEncodingOptions) -> Self {
+        Self {
+            stdout: BufWriter::new(std::io::stdout().lock()),
+            encoding: text_encoding_options.output_encoding,
+            policy: text_encoding_options.encoding_errors,
+            num_lines: 0,
+            error: None,
+        }
+    }
+
+    fn print_line(&mut self, line: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        if self.num_lines == 0 {
+            self.write_bytes(byte_order_mark(self.encoding));
+        }
+        match encode_text(line, self.encoding, self.policy) {
+            Ok(encoded) => {
+                self.num_lines += 1;
+                self.write_bytes(&encoded);
+                self.write_bytes(b"\n");
+            }
+            Err(why) => {
+                self.error = Some(CliError::Io(format!(
+                    "couldn't encode output: {}",
+                    why.with_offset(self.num_lines, 0)
+                )))
+            }
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.error.is_none() {
+            if let Err(why) = self.stdout.write_all(bytes) {
+                self.error = Some(CliError::Io(format!("couldn't write output: {}", why)));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.error.is_none() {
+            if let Err(why) = self.stdout.flush() {
+                self.error = Some(CliError::Io(format!("couldn't write output: {}", why)));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Result<(), CliError> {
+        self.flush();
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Shows how many names have been divided on stderr, at most once a second.
+struct Progress {
+    enabled: bool,
+    start: Instant,
+    last_shown: Instant,
+    num_names: usize,
+}
+
+impl Progress {
+    fn new(enabled: bool) -> Self {
+        let now = Instant::now();
+        Self {
+            enabled,
+            start: now,
+            last_shown: now,
+            num_names: 0,
+        }
+    }
+
+    fn add(&mut self, num_names: usize) {
+        self.num_names += num_names;
+        if self.enabled && self.last_shown.elapsed() >= Duration::from_secs(1) {
+            self.last_shown = Instant::now();
+            self.show();
+        }
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            self.show();
+            eprintln!();
+        }
+    }
+
+    fn show(&self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        eprint!(
+            "\r{} names divided ({:.0} names/s)",
+            self.num_names,
+            self.num_names as f64 / elapsed.max(f64::EPSILON)
+        );
+    }
+}
+
+/// Opens `input_path` for streaming, or stdin for `-`.
+fn open_input(input_path: &String) -> Result<Box<dyn Read + Send>, CliError> {
+    if input_path == "-" {
+        Ok(Box::new(std::io::stdin()))
+    } else {
+        let file = File::open(input_path)
+            .map_err(|why| CliError::Io(format!("couldn't open {}: {}", input_path, why)))?;
+        Ok(Box::new(file))
+    }
+}
+
+/// Streams the lines of `input_path` in batches for `process_in_order`.
+///
+/// Reading stops at the first invalid batch, whose error is left in `read_error` to be
+/// returned once the names read so far are written.
+fn read_line_batches<'a>(
+    input_path: &'a String,
+    text_encoding_options: &TextEncodingOptions,
+    read_error: &'a Mutex<Option<CliError>>,
+) -> Result<impl Iterator<Item = Vec<String>> + Send + 'a, CliError> {
+    let batches = LineBatches::new(
+        open_input(input_path)?,
+        text_encoding_options.input_encoding,
+        text_encoding_options.encoding_errors,
+        BATCH_SIZE,
+    );
+    Ok(batches.map_while(move |batch| match batch {
+        Ok(batch) => Some(batch),
+        Err(why) => {
+            *read_error.lock().unwrap() = Some(CliError::Io(format!(
+                "couldn't read {}: {}",
+                input_path, why
+            )));
+            None
+        }
+    }))
+}
+
+/// The error left by `read_line_batches`, if reading stopped early.
+fn take_read_error(read_error: Mutex<Option<CliError>>) -> Result<(), CliError> {
+    match read_error.into_inner().unwrap() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+#[derive(Args)]
+struct WorkerOptions {
+    /// Threads dividing names (default: the number of CPUs); output keeps the input order
+    #[clap(long)]
+    workers: Option<usize>,
+    /// Show the number of divided names on stderr
+    #[clap(long)]
+    progress: bool,
+}
+
+impl WorkerOptions {
+    fn num_workers(&self) -> usize {
+        self.workers.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, |num_threads| num_threads.get())
+        })
+    }
+}
+
+/// How the family and the given name are written in a divided name corpus.
+#[derive(Args)]
+struct GoldFileOptions {
+    /// Separator between the family and the given name in the gold file
+    #[clap(long, default_value = " ")]
+    gold_separator: String,
+    /// Read the gold file as TSV with the family and the given name in these 0-based
+    /// columns, such as `1,2`
+    #[clap(long, value_delimiter = ',')]
+    gold_columns: Option<Vec<usize>>,
+}
+
+impl GoldFileOptions {
+    /// Checks the options before any line is read.
+    fn check(&self) -> Result<(), CliError> {
+        match self.gold_columns.as_deref() {
+            None | Some([_, _]) => Ok(()),
+            Some(_) => Err(CliError::Usage(
+                "--gold-columns needs the family and the given name column".to_string(),
+            )),
+        }
+    }
+
+    /// Reads the gold name of a line, if it has one.
+    fn parse_line(&self, line: &str) -> Option<GoldName> {
+        match self.gold_columns.as_deref() {
+            Some(&[family_column, given_column]) => {
+                parse_divided_name_columns(line, family_column, given_column)
+            }
+            _ => parse_divided_name(line, &self.gold_separator),
+        }
+    }
+
+    /// Reads the gold names of a file, skipping the lines without one.
+    fn read(
+        &self,
+        file_path: &String,
+        text_encoding_options: &TextEncodingOptions,
+    ) -> Result<Vec<GoldName>, CliError> {
+        self.check()?;
+        Ok(read_names_file(file_path, text_encoding_options)?
+            .lines()
+            .filter_map(|line| self.parse_line(line))
+            .collect())
+    }
+}
+
+fn parse_delimiter(delimiter: &str) -> Result<u8, CliError> {
+    match delimiter {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ if delimiter.len() == 1 => Ok(delimiter.as_bytes()[0]),
+        _ => Err(CliError::Usage(format!(
+            "--delimiter must be a single ASCII char or `tab`: {}",
+            delimiter
+        ))),
+    }
+}
+
+/// Bytes written by a `csv::Writer` that can be taken out while it keeps writing.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Formats CSV records, quoting fields as needed, with one writer for all the records
+/// of a file.
+struct CsvRecordFormatter {
+    writer: csv::Writer<SharedBuffer>,
+    buffer: SharedBuffer,
+}
+
+impl CsvRecordFormatter {
+    fn new(delimiter: u8) -> Self {
+        let buffer = SharedBuffer::default();
+        let writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .terminator(csv::Terminator::Any(b'\n'))
+            .flexible(true)
+            .from_writer(buffer.clone());
+        Self { writer, buffer }
+    }
+
+    /// Formats one record without the line terminator.
+    fn format(&mut self, fields: &[String]) -> String {
+        // Records of any length are written to memory, which can't fail.
+        self.writer
+            .write_record(fields)
+            .unwrap_or_else(|why| panic!("couldn't write a record: {}", why));
+        self.writer
+            .flush()
+            .unwrap_or_else(|why| panic!("couldn't write a record: {}", why));
+        let mut record = String::from_utf8(self.buffer.0.take()).unwrap();
+        record.pop();
+        record
+    }
+}
+
+/// Where the names to divide are in the records of a CSV file.
+struct CsvNameColumn {
+    column_idx: usize,
+    /// Number of fields of the header, which shorter records are padded to so that the
+    /// appended columns stay under their headers; 0 without a header.
+    num_input_columns: usize,
+}
+
+impl CsvNameColumn {
+    /// Finds `column` by name in `header`, or by its 0-based index in a file without a
+    /// header, so that a header named like an index is never mistaken for one.
+    fn find(header: Option<&[String]>, column: &str, input: &str) -> Result<Self, CliError> {
+        match header {
+            Some(header) => {
+                let column_idx =
+                    header
+                        .iter()
+                        .position(|field| field == column)
+                        .ok_or_else(|| {
+                            CliError::Usage(format!(
+                                "no column {} in the header of {}",
+                                column, input
+                            ))
+                        })?;
+                Ok(Self {
+                    column_idx,
+                    num_input_columns: header.len(),
+                })
+            }
+            None => {
+                let column_idx = column.parse::<usize>().map_err(|_| {
+                    CliError::Usage(format!(
+                        "--column must be an index with --no-header: {}",
+                        column
+                    ))
+                })?;
+                Ok(Self {
+                    column_idx,
+                    num_input_columns: 0,
+                })
+            }
+        }
+    }
+
+    /// Appends the family name, the given name, the score and the algorithm of the name
+    /// in `fields`. Names too short to divide get empty columns instead of stopping the
+    /// run, with why for those that aren't empty.
+    fn divide(
+        &self,
+        divider: &dyn NameDivider,
+        mut fields: Vec<String>,
+    ) -> (Vec<String>, Option<String>) {
+        if fields.len() < self.num_input_columns {
+            fields.resize(self.num_input_columns, String::new());
+        }
+        let undivided_name = fields
+            .get(self.column_idx)
+            .map_or("", |field| field.trim())
+            .to_string();
+        let mut failure = None;
+        match divider.try_divide_name(&undivided_name) {
+            Ok(divided_name) => {
+                let algorithm = divided_name.algorithm();
+                fields.extend([
+                    divided_name.family,
+                    divided_name.given,
+                    divided_name.score.to_string(),
+                    algorithm,
+                ]);
+            }
+            Err(why) => {
+                fields.resize(fields.len() + 4, String::new());
+                if !undivided_name.is_empty() {
+                    failure = Some(divide_error(&undivided_name, why));
+                }
+            }
+        }
+        (fields, failure)
+    }
+}
+
+#[derive(Serialize)]
+struct Mismatch {
+    /// The line of the gold file.
+    gold: String,
+    divided: String,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct JsonAccuracyReport {
+    #[serde(flatten)]
+    report: AccuracyReport,
+    num_skipped_lines: usize,
+    mismatches: Vec<Mismatch>,
+}
+
+fn format_accuracy(num_correct: usize, num_names: usize, accuracy: f64) -> String {
+    format!("{:.4} ({}/{})", accuracy, num_correct, num_names)
+}
+
+fn format_optional(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.4}", value))
+}
+
+fn format_accuracy_report(report: &AccuracyReport, num_skipped_lines: usize) -> Vec<String> {
+    let mut lines = vec![format!(
+        "accuracy: {}",
+        format_accuracy(report.num_correct, report.num_names, report.accuracy)
+    )];
+    if num_skipped_lines > 0 {
+        lines.push(format!(
+            "skipped {} lines without a family and a given name",
+            num_skipped_lines
+        ));
+    }
+    for (title, buckets) in [
+        ("by length", &report.by_length),
+        ("by family+given length", &report.by_family_given_length),
+        ("by provenance", &report.by_provenance),
+    ] {
+        lines.push(String::new());
+        lines.push(title.to_string());
+        for bucket in buckets {
+            lines.push(format!(
+                "  {}: {}",
+                bucket.label,
+                format_accuracy(bucket.num_correct, bucket.num_names, bucket.accuracy)
+            ));
+        }
+    }
+    lines.push(String::new());
+    lines.push("coverage (score >= threshold)".to_string());
+    for point in &report.coverage {
+        lines.push(format!(
+            "  {}: coverage {:.4} ({} names), accuracy {}",
+            point.threshold,
+            point.coverage,
+            point.num_names,
+            format_optional(point.accuracy)
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "calibration (expected calibration error {:.4})",
+        report.expected_calibration_error
+    ));
+    for bin in &report.calibration {
+        lines.push(format!(
+            "  [{:.2}, {:.2}{}: {} names, mean score {}, accuracy {}",
+            bin.lower,
+            bin.upper,
+            if bin.upper >= 1.0 { "]" } else { ")" },
+            bin.num_names,
+            format_optional(bin.mean_score),
+            format_optional(bin.accuracy)
+        ));
+    }
+    lines.push(String::new());
+    lines.push("top-k accuracy".to_string());
+    for top_k_accuracy in &report.top_k {
+        lines.push(format!(
+            "  top-{}: {}",
+            top_k_accuracy.k,
+            format_accuracy(
+                top_k_accuracy.num_correct,
+                report.num_names,
+                top_k_accuracy.accuracy
+            )
+        ));
+    }
+    lines
+}
+
+const REPL_QUIT_COMMANDS: [&str; 3] = [":quit", ":q", ":exit"];
+/// Candidates shown by `:candidates` without a number.
+const REPL_DEFAULT_NUM_CANDIDATES: usize = 5;
+const REPL_HELP: &str = "\
+Type a name to divide it, or a command:
+  :mode [MODE]          show or switch the mode (basic, gbdt, logistic, ngram, two_char)
+  :candidates [N|off]   show the top N candidates (default 5); without N, toggle
+  :explain [on|off]     show the provenance, normalized form, spans and features
+  :normalize [on|off]   replace old glyph forms such as 髙 before dividing
+  :status               show the current settings
+  :help                 show this help
+  :quit                 leave";
+
+/// The settings of the `repl` subcommand and the dividers built for them.
+struct Repl {
+    mode: Mode,
+    options: DividerOptions,
+    num_candidates: usize,
+    explain: bool,
+    /// Dividers already built, by mode and whether they normalize, so that switching
+    /// back to one costs nothing.
+    dividers: HashMap<(Mode, bool), Box<dyn NameDivider + Send + Sync>>,
+    feature_extractor: FamilyRankingFeatureExtractor,
+}
+
+/// Reads `on`, `off`, or nothing to flip `current`.
+fn parse_switch(argument: Option<&str>, current: bool) -> Result<bool, String> {
+    match argument {
+        None => Ok(!current),
+        Some("on") => Ok(true),
+        Some("off") => Ok(false),
+        Some(argument) => Err(format!("expected on or off: {}", argument)),
+    }
+}
+
+fn format_switch(switch: bool) -> &'static str {
+    if switch {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl Repl {
+    /// Starts with the divider of `mode`, so that bad options end the command before
+    /// the first prompt.
+    fn new(mode: Mode, options: DividerOptions) -> Result<Self, CliError> {
+        let mut repl = Self {
+            mode,
+            options,
+            num_candidates: 0,
+            explain: false,
+            dividers: HashMap::new(),
+            feature_extractor: FamilyRankingFeatureExtractor::shared(),
+        };
+        let divider = create_divider(mode, &repl.options)?;
+        repl.dividers
+            .insert((mode, !repl.options.no_normalize), divider);
+        Ok(repl)
+    }
+
+    /// Builds the divider of `mode` with the current options unless it is built already.
+    fn prepare_divider(&mut self, mode: Mode) -> Result<(), String> {
+        let key = (mode, !self.options.no_normalize);
+        if !self.dividers.contains_key(&key) {
+            let divider = create_divider(mode, &self.options).map_err(|why| why.to_string())?;
+            self.dividers.insert(key, divider);
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        format!(
+            "mode: {}, candidates: {}, explain: {}, normalize: {}",
+            self.mode,
+            self.num_candidates,
+            format_switch(self.explain),
+            format_switch(!self.options.no_normalize)
+        )
+    }
+
+    fn run_command(&mut self, line: &str) -> Vec<String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.next();
+        let result = match command {
+            ":mode" => match argument {
+                None => Ok(format!("mode: {}", self.mode)),
+                Some(mode) => parse_mode(mode).and_then(|mode| {
+                    self.prepare_divider(mode).map(|_| {
+                        self.mode = mode;
+                        format!("mode: {}", self.mode)
+                    })
+                }),
+            },
+            ":candidates" => {
+                let num_candidates = match argument {
+                    None if self.num_candidates == 0 => Ok(REPL_DEFAULT_NUM_CANDIDATES),
+                    None | Some("off") => Ok(0),
+                    Some(argument) => argument
+                        .parse::<usize>()
+                        .map_err(|_| format!("expected a number or off: {}", argument)),
+                };
+                num_candidates.map(|num_candidates| {
+                    self.num_candidates = num_candidates;
+                    format!("candidates: {}", self.num_candidates)
+                })
+            }
+            ":explain" => parse_switch(argument, self.explain).map(|explain| {
+                self.explain = explain;
+                format!("explain: {}", format_switch(self.explain))
+            }),
+            ":normalize" => {
+                parse_switch(argument, !self.options.no_normalize).and_then(|normalize| {
+                    let no_normalize = self.options.no_normalize;
+                    self.options.no_normalize = !normalize;
+                    if let Err(why) = self.prepare_divider(self.mode) {
+                        self.options.no_normalize = no_normalize;
+                        return Err(why);
+                    }
+                    Ok(format!("normalize: {}", format_switch(normalize)))
+                })
+            }
+            ":status" => Ok(self.status()),
+            ":help" => Ok(REPL_HELP.to_string()),
+            _ => Err(format!("unknown command: {} (see :help)", command)),
+        };
+        vec![result.unwrap_or_else(|why| format!("error: {}", why))]
+    }
+
+    fn divide(&self, undivided_name: &str) -> Vec<String> {
+        let divider = &self.dividers[&(self.mode, !self.options.no_normalize)];
+        let divided_name = match divider.try_divide_name(undivided_name) {
+            Ok(divided_name) => divided_name,
+            Err(why) => return vec![format!("error: {}", why)],
+        };
+        let mut lines = vec![format!(
+            "{}{}{}\t{:.4}\t{}",
+            divided_name.family,
+            divided_name.separator,
+            divided_name.given,
+            divided_name.score,
+            divided_name.algorithm()
+        )];
+        if self.num_candidates > 0 {
+            let candidates = divider.divide_name_candidates(&undivided_name.to_string());
+            for (rank, candidate) in candidates.iter().take(self.num_candidates).enumerate() {
+                lines.push(format!(
+                    "  {}. {}{}{}\t{:.4}",
+                    rank + 1,
+                    candidate.family,
+                    candidate.separator,
+                    candidate.given,
+                    candidate.score
+                ));
+            }
+        }
+        if self.explain {
+            lines.extend(self.explain(&divided_name));
+        }
+        lines
+    }
+
+    fn explain(&self, divided_name: &DividedName) -> Vec<String> {
+        let attributes: Vec<String> = divided_name
+            .provenance
+            .attributes()
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        let mut lines = vec![
+            format!("  provenance: {}", attributes.join(", ")),
+            format!(
+                "  normalized: {} {}",
+                divided_name.normalized_family, divided_name.normalized_given
+            ),
+            format!(
+                "  spans: family chars {}..{} (bytes {}..{}), given chars {}..{} (bytes {}..{})",
+                divided_name.family_span.char_start,
+                divided_name.family_span.char_end,
+                divided_name.family_span.byte_start,
+                divided_name.family_span.byte_end,
+                divided_name.given_span.char_start,
+                divided_name.given_span.char_end,
+                divided_name.given_span.byte_start,
+                divided_name.given_span.byte_end
+            ),
+        ];
+        // The features come from the embedded assets, so they would mislead with --assets.
+        let is_scored_on_kanji = matches!(
+            divided_name.provenance,
+            Provenance::Basic | Provenance::Gbdt { .. }
+        );
+        if is_scored_on_kanji && self.options.assets.is_none() {
+            let features = self.feature_extractor.get_features(
+                &divided_name.normalized_family,
+                &divided_name.normalized_given,
+            );
+            lines.push(format!(
+                "  family rank: {}, order score: {:.4} + {:.4}, length score: {:.4} + {:.4}",
+                features.rank,
+                features.family_order_score,
+                features.given_order_score,
+                features.family_length_score,
+                features.given_length_score
+            ));
+        }
+        for warning in divided_name_warnings(divided_name) {
+            lines.push(format!("  warning: {}", warning));
+        }
+        lines
+    }
+}
+
+const REVIEW_HELP: &str = "Enter: accept 1, a number: pick that candidate, \
+a family and a given name separated by a space: correct, s: skip, q: quit";
+
+/// What the reviewer answered for one name in the `review` subcommand.
+#[derive(Debug, PartialEq)]
+enum ReviewAnswer {
+    /// The candidate at this index
+    Pick(usize),
+    /// The family and the given name typed in
+    Correct(String, String),
+    Skip,
+    Quit,
+}
+
+fn parse_review_answer(
+    answer: &str,
+    undivided_name: &str,
+    num_candidates: usize,
+) -> Result<ReviewAnswer, &'static str> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(ReviewAnswer::Pick(0));
+    }
+    if answer == "s" {
+        return Ok(ReviewAnswer::Skip);
+    }
+    if answer == "q" {
+        return Ok(ReviewAnswer::Quit);
+    }
+    if let Ok(number) = answer.parse::<usize>() {
+        if number == 0 || number > num_candidates {
+            return Err("No candidate has that number.");
+        }
+        return Ok(ReviewAnswer::Pick(number - 1));
+    }
+    let gold_name = parse_divided_name(answer, " ")
+        .ok_or("Separate the family and the given name by a space.")?;
+    if gold_name.family.clone() + &gold_name.given != undivided_name {
+        return Err("The family and the given name must make up the name.");
+    }
+    Ok(ReviewAnswer::Correct(gold_name.family, gold_name.given))
+}
+
+#[derive(Serialize)]
+struct ComparedDivision {
+    family: String,
+    given: String,
+    score: f64,
+    algorithm: String,
+}
+
+impl ComparedDivision {
+    fn new(divided_name: DividedName) -> Self {
+        Self {
+            algorithm: divided_name.algorithm(),
+            family: divided_name.family,
+            given: divided_name.given,
+            score: divided_name.score,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Difference {
+    name: String,
+    a: ComparedDivision,
+    b: ComparedDivision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gold: Option<String>,
+}
+
+/// How the two dividers do against the gold names.
+#[derive(Serialize, Default)]
+struct GoldComparison {
+    num_correct_a: usize,
+    num_correct_b: usize,
+    accuracy_a: f64,
+    accuracy_b: f64,
+    only_a_correct: usize,
+    only_b_correct: usize,
+    both_wrong: usize,
+}
+
+#[derive(Serialize)]
+struct Comparison {
+    divider_a: String,
+    divider_b: String,
+    num_names: usize,
+    num_agreed: usize,
+    agreement: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gold: Option<GoldComparison>,
+    differences: Vec<Difference>,
+    /// Separator the gold names of the differences are written with.
+    #[serde(skip)]
+    gold_separator: String,
+}
+
+impl Comparison {
+    fn add(
+        &mut self,
+        undivided_name: String,
+        divided_name_a: DividedName,
+        divided_name_b: DividedName,
+        gold_name: Option<GoldName>,
+    ) {
+        self.num_names += 1;
+        let is_agreed = divided_name_a.family == divided_name_b.family;
+        self.num_agreed += is_agreed as usize;
+        self.agreement = self.num_agreed as f64 / self.num_names as f64;
+        if let (Some(gold_comparison), Some(gold_name)) = (&mut self.gold, &gold_name) {
+            let is_correct_a = divided_name_a.family == gold_name.family;
+            let is_correct_b = divided_name_b.family == gold_name.family;
+            gold_comparison.num_correct_a += is_correct_a as usize;
+            gold_comparison.num_correct_b += is_correct_b as usize;
+            gold_comparison.only_a_correct += (is_correct_a && !is_correct_b) as usize;
+            gold_comparison.only_b_correct += (!is_correct_a && is_correct_b) as usize;
+            gold_comparison.both_wrong += (!is_correct_a && !is_correct_b) as usize;
+            gold_comparison.accuracy_a =
+                gold_comparison.num_correct_a as f64 / self.num_names as f64;
+            gold_comparison.accuracy_b =
+                gold_comparison.num_correct_b as f64 / self.num_names as f64;
+        }
+        if !is_agreed {
+            self.differences.push(Difference {
+                name: undivided_name,
+                a: ComparedDivision::new(divided_name_a),
+                b: ComparedDivision::new(divided_name_b),
+                gold: gold_name
+                    .map(|gold_name| gold_name.family + &self.gold_separator + &gold_name.given),
+            });
+        }
+    }
+}
+
+fn format_comparison(comparison: &Comparison) -> Vec<String> {
+    let mut lines = vec![];
+    for difference in &comparison.differences {
+        let mut line = format!(
+            "{}\tA: {} {} ({:.4})\tB: {} {} ({:.4})",
+            difference.name,
+            difference.a.family,
+            difference.a.given,
+            difference.a.score,
+            difference.b.family,
+            difference.b.given,
+            difference.b.score
+        );
+        if let Some(gold) = &difference.gold {
+            line += &format!("\tgold: {}", gold);
+        }
+        lines.push(line);
+    }
+    lines.push(format!(
+        "agreement: {:.4} ({}/{}), A: {}, B: {}",
+        comparison.agreement,
+        comparison.num_agreed,
+        comparison.num_names,
+        comparison.divider_a,
+        comparison.divider_b
+    ));
+    if let Some(gold_comparison) = &comparison.gold {
+        lines.push(format!(
+            "accuracy A: {:.4} ({}/{}), B: {:.4} ({}/{})",
+            gold_comparison.accuracy_a,
+            gold_comparison.num_correct_a,
+            comparison.num_names,
+            gold_comparison.accuracy_b,
+            gold_comparison.num_correct_b,
+            comparison.num_names
+        ));
+        lines.push(format!(
+            "only A correct: {}, only B correct: {}, both wrong: {}",
+            gold_comparison.only_a_correct,
+            gold_comparison.only_b_correct,
+            gold_comparison.both_wrong
+        ));
+    }
+    lines
+}
+
+#[derive(Serialize)]
+struct JsonParityReport {
+    #[serde(flatten)]
+    report: ParityReport,
+    num_skipped_lines: usize,
+}
+
+fn format_parity_report(report: &ParityReport, num_skipped_lines: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for difference in &report.differences {
+        lines.push(format!(
+            "{}\tpython: {} {} ({:.4}, {})\trust: {} {} ({:.4}, {})\tdelta: {:+.4}",
+            difference.name,
+            difference.reference_family,
+            difference.reference_given,
+            difference.reference_score,
+            difference.reference_algorithm.as_deref().unwrap_or("-"),
+            difference.family,
+            difference.given,
+            difference.score,
+            difference.algorithm,
+            difference.score_delta
+        ));
+    }
+    lines.push(format!(
+        "split agreement: {}",
+        format_accuracy(
+            report.num_same_split,
+            report.num_names,
+            report.split_agreement
+        )
+    ));
+    lines.push(format!(
+        "same algorithm: {}/{}",
+        report.num_same_algorithm, report.num_with_algorithm
+    ));
+    lines.push(format!(
+        "score within {}: {}/{} of the same splits",
+        report.tolerance, report.num_within_tolerance, report.num_same_split
+    ));
+    lines.push(format!(
+        "score delta of the same splits: mean {:+.4}, mean absolute {:.4}, max absolute {:.4}",
+        report.mean_score_delta, report.mean_abs_score_delta, report.max_abs_score_delta
+    ));
+    if num_skipped_lines > 0 {
+        lines.push(format!(
+            "skipped {} lines without a name to divide",
+            num_skipped_lines
+        ));
+    }
+    lines
+}
+
+/// Maps the bundle at `assets_path`, written by `compile-assets`, for the repositories to
+/// look up from.
+fn load_compiled_assets(assets_path: &String) -> Result<CompiledAssets, CliError> {
+    let mapped_assets = map_compiled_assets(assets_path)
+        .map_err(|why| CliError::Io(format!("couldn't open {}: {}", assets_path, why)))?;
+    CompiledAssets::from_bytes(mapped_assets)
+        .map_err(|why| CliError::Io(format!("couldn't parse {}: {}", assets_path, why)))
+}
+
+/// The feature extractor of the GBDT mode, on the kanji statistics and family names
+/// of `assets` written by `compile-assets`, or on the embedded ones.
+fn load_feature_extractor(
+    assets: &Option<String>,
+) -> Result<FamilyRankingFeatureExtractor, CliError> {
+    match assets {
+        Some(assets_path) => {
+            let compiled_assets = load_compiled_assets(assets_path)?;
+            Ok(FamilyRankingFeatureExtractor {
+                kanji_statistics_repository: Arc::new(KanjiStatisticsRepository::from_compiled(
+                    &compiled_assets,
+                )),
+                family_name_repository: Arc::new(FamilyNameRepository::from_compiled(
+                    &compiled_assets,
+                )),
+            })
+        }
+        None => Ok(FamilyRankingFeatureExtractor::shared()),
+    }
+}
+
+#[derive(Serialize)]
+struct LookedUpChar {
+    kanji: char,
+    /// First, middle and last char of the family name, then of the given name
+    order_counts: Vec<i32>,
+    /// In a family name of 1, 2, 3 and 4 or more chars, then in a given name
+    length_counts: Vec<i32>,
+    /// The char has no statistics and is scored with the all-zero `default_kanji`
+    is_default_kanji: bool,
+}
+
+#[derive(Serialize)]
+struct LookedUpFamily {
+    family: String,
+    /// Rank in the family name list, from 0 for the most common; none when not listed
+    rank: Option<usize>,
+}
+
+/// The data behind the division of a name, for the `lookup` subcommand.
+#[derive(Serialize)]
+struct Lookup {
+    name: String,
+    normalized_name: String,
+    chars: Vec<LookedUpChar>,
+    /// Every prefix that could be the family name, shortest first
+    family_candidates: Vec<LookedUpFamily>,
+}
+
+fn lookup_name(
+    name: &str,
+    normalize_name: bool,
+    feature_extractor: &FamilyRankingFeatureExtractor,
+) -> Lookup {
+    let holder =
+        UndividedNameHolder::new_with_normalization(name.to_string(), normalize_name, None);
+    let kanji_statistics_repository = &feature_extractor.kanji_statistics_repository;
+    let chars = holder
+        .normalized_name
+        .chars()
+        .map(|kanji| {
+            let kanji_statistics = kanji_statistics_repository.get(&kanji.to_string());
+            LookedUpChar {
+                kanji,
+                order_counts: kanji_statistics.order_counts.clone(),
+                length_counts: kanji_statistics.length_counts.clone(),
+                is_default_kanji: !kanji_statistics_repository.contains(kanji),
+            }
+        })
+        .collect();
+    let family_candidates = (1..holder.normalized_name.chars().count())
+        .map(|family_length| {
+            let family: String = holder.normalized_name.chars().take(family_length).collect();
+            let rank = feature_extractor.family_name_repository.get_rank(&family);
+            LookedUpFamily {
+                family,
+                rank: if rank.is_nan() {
+                    None
+                } else {
+                    Some(rank as usize)
+                },
+            }
+        })
+        .collect();
+    Lookup {
+        name: name.to_string(),
+        normalized_name: holder.normalized_name,
+        chars,
+        family_candidates,
+    }
+}
+
+fn format_lookup(lookup: &Lookup) -> Vec<String> {
+    let join = |counts: &[i32]| {
+        counts
+            .iter()
+            .map(|count| count.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+    let mut lines = vec![if lookup.normalized_name == lookup.name {
+        lookup.name.clone()
+    } else {
+        format!("{} (normalized: {})", lookup.name, lookup.normalized_name)
+    }];
+    lines.push(
+        "char\torder: family first/middle/last | given first/middle/last\tlength: family 1/2/3/4+ | given 1/2/3/4+"
+            .to_string(),
+    );
+    for looked_up_char in &lookup.chars {
+        let (order_counts, length_counts) =
+            (&looked_up_char.order_counts, &looked_up_char.length_counts);
+        let mut line = format!(
+            "{}\t{} | {}\t{} | {}",
+            looked_up_char.kanji,
+            join(&order_counts[..3]),
+            join(&order_counts[3..]),
+            join(&length_counts[..4]),
+            join(&length_counts[4..])
+        );
+        if looked_up_char.is_default_kanji {
+            line.push_str("\tno statistics, scored as default_kanji");
+        }
+        lines.push(line);
+    }
+    lines.push("family\trank".to_string());
+    for family_candidate in &lookup.family_candidates {
+        lines.push(match family_candidate.rank {
+            Some(rank) => format!("{}\t{}", family_candidate.family, rank),
+            None => format!("{}\t-", family_candidate.family),
+        });
+    }
+    lines
+}
+
+/// Splits gold names into candidates the way the GBDT mode with `options` does.
+fn gbdt_divider_base(options: &DividerOptions) -> Result<NameDividerBase, CliError> {
+    let mut name_divider_base = NameDividerBase::new(
+        options.separator.clone(),
+        !options.no_normalize,
+        Provenance::Gbdt {
+            model_version: EMBEDDED_GBDT_MODEL_VERSION.to_string(),
+        },
+    );
+    name_divider_base.gaiji_mapping = load_gaiji_mapping(options)?;
+    Ok(name_divider_base)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FeatureFormat {
+    Csv,
+    Tsv,
+    /// `label qid:group_id 1:rank 2:fullname_length ...`, without a header
+    #[value(name = "libsvm")]
+    LibSvm,
+}
+
+fn format_feature_header(format: FeatureFormat) -> Option<String> {
+    let delimiter = match format {
+        FeatureFormat::Csv => ",",
+        FeatureFormat::Tsv => "\t",
+        FeatureFormat::LibSvm => return None,
+    };
+    let mut columns = vec!["group_id", "family", "given", "label"];
+    columns.extend(FAMILY_RANKING_FEATURE_NAMES);
+    Some(columns.join(delimiter))
 }
 
-fn create_divider(mode: &String) -> Box<dyn NameDivider> {
-    if mode == "basic" {
-        let basic_divider =
-            get_basic_name_divider(" ".to_string(), true, "kanji_feature".to_string(), false);
-        Box::new(basic_divider)
+/// Formats the rows; a missing family rank is written as NaN, which pandas and LightGBM
+/// read as missing.
+fn format_feature_rows(rows: &[FeatureRow], format: FeatureFormat) -> Vec<String> {
+    let delimiter = if format == FeatureFormat::Csv {
+        b','
     } else {
-        let gbdt_divider =
-            get_gbdt_name_divider(" ".to_string(), true, "kanji_feature".to_string());
-        Box::new(gbdt_divider)
+        b'\t'
+    };
+    let mut formatter = CsvRecordFormatter::new(delimiter);
+    rows.iter()
+        .map(|row| {
+            let label = (row.is_gold as i32).to_string();
+            match format {
+                FeatureFormat::Csv | FeatureFormat::Tsv => {
+                    let mut fields = vec![
+                        row.group_id.to_string(),
+                        row.family.clone(),
+                        row.given.clone(),
+                        label,
+                    ];
+                    fields.extend(row.features.iter().map(|value| value.to_string()));
+                    formatter.format(&fields)
+                }
+                FeatureFormat::LibSvm => {
+                    let mut fields = vec![label, format!("qid:{}", row.group_id)];
+                    fields.extend(
+                        row.features
+                            .iter()
+                            .enumerate()
+                            .map(|(i, value)| format!("{}:{}", i + 1, value)),
+                    );
+                    fields.join(" ")
+                }
+            }
+        })
+        .collect()
+}
+
+fn embedded_asset(file_name: &str) -> String {
+    let asset = Asset::get(file_name).unwrap_or_else(|| panic!("{} is not embedded", file_name));
+    String::from_utf8_lossy(asset.data.as_ref()).to_string()
+}
+
+#[derive(Parser, Clone)]
+struct DividerOptions {
+    /// Order/length weights for the basic mode, written by `fit-weights`
+    #[clap(long)]
+    weights: Option<String>,
+    /// Model file for the logistic mode (`train-logistic`) or the ngram mode (`build-ngram`)
+    #[clap(long)]
+    model: Option<String>,
+    /// Assets for the basic and gbdt modes, written by `compile-assets`
+    #[clap(long)]
+    assets: Option<String>,
+    /// LightGBM model text for the gbdt mode instead of the embedded one
+    #[clap(long)]
+    gbdt_model: Option<String>,
+    /// Gaiji mapping: per line, a Private Use Area char, a tab and its standard char
+    #[clap(long)]
+    gaiji: Option<String>,
+    /// Separator between the family and the given name
+    #[clap(long, default_value = " ")]
+    separator: String,
+    /// Divide names as written, without replacing old glyph forms such as 髙
+    #[clap(long)]
+    no_normalize: bool,
+    /// Score 4-char names of the basic mode on the order of their chars only, as in
+    /// namedivider-python
+    #[clap(long)]
+    only_order_score_when_4: bool,
+    /// Names divided by hand: per line, the family and the given name separated by a space
+    #[clap(long)]
+    user_dictionary: Option<String>,
+}
+
+/// Reads a divider for `compare`: a mode, then comma-separated options named like the
+/// divider flags, such as `gbdt,gbdt-model=model_v2.txt` or `basic,weights=weights.json`.
+/// The switches are given without a value, as in `basic,no-normalize`.
+fn parse_divider_spec(spec: &str) -> Result<(Mode, DividerOptions), CliError> {
+    let mut parts = spec.split(',');
+    let mode = parse_mode(parts.next().unwrap_or_default()).map_err(CliError::Usage)?;
+    let argv = ["divider".to_string()]
+        .into_iter()
+        .chain(parts.map(|part| format!("--{}", part)));
+    let options = DividerOptions::try_parse_from(argv).map_err(|why| {
+        // The first line of a clap error says what is wrong; the rest is usage help.
+        let why = why.to_string();
+        let why = why.lines().next().unwrap_or_default();
+        CliError::Usage(format!(
+            "invalid divider {}: {}",
+            spec,
+            why.trim_start_matches("error: ")
+        ))
+    })?;
+    Ok((mode, options))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// The family and the given name joined by the separator
+    Text,
+    /// A header line, then the fields of each divided name separated by tabs
+    Tsv,
+    /// A JSON object, or an array of them for a file
+    Json,
+    /// One JSON object per line
+    Jsonl,
+}
+
+#[derive(Args)]
+struct OutputOptions {
+    /// Output format
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Also write up to this many candidate splits, most probable first (tsv, json and jsonl)
+    #[clap(long, default_value_t = 0)]
+    candidates: usize,
+    /// Also warn on names divided without a usable score and on gaiji without a mapping
+    #[clap(long)]
+    warnings: bool,
+}
+
+#[derive(Serialize)]
+struct OutputCandidate {
+    family: String,
+    given: String,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct OutputDividedName {
+    family: String,
+    given: String,
+    separator: String,
+    score: f64,
+    algorithm: String,
+    provenance: Provenance,
+    normalized_family: String,
+    normalized_given: String,
+    family_span: Span,
+    given_span: Span,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<OutputCandidate>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+const TSV_COLUMNS: [&str; 7] = [
+    "family",
+    "given",
+    "score",
+    "algorithm",
+    "provenance",
+    "normalized_family",
+    "normalized_given",
+];
+
+impl OutputOptions {
+    /// The line written before the divided names, if the format has one.
+    fn header(&self) -> Option<String> {
+        match self.format {
+            OutputFormat::Tsv if self.candidates > 0 => {
+                Some(TSV_COLUMNS.join("\t") + "\tcandidates")
+            }
+            OutputFormat::Tsv => Some(TSV_COLUMNS.join("\t")),
+            _ => None,
+        }
+    }
+
+    /// The line written in place of a name that couldn't be divided, which keeps the
+    /// output lines in step with the input lines.
+    fn placeholder(&self) -> String {
+        match self.format {
+            OutputFormat::Text | OutputFormat::Tsv => String::new(),
+            OutputFormat::Json | OutputFormat::Jsonl => "null".to_string(),
+        }
+    }
+
+    /// Divides `undivided_name` into its output and the warnings for stderr. The
+    /// warnings are part of the output in the JSON formats, where `pretty` spreads the
+    /// object over several lines.
+    fn divide(
+        &self,
+        divider: &dyn NameDivider,
+        undivided_name: &str,
+        encoding_check: &Option<(EncodingTarget, VariantTable)>,
+        pretty: bool,
+    ) -> Result<(String, Vec<String>), String> {
+        let divide_error = |why| divide_error(undivided_name, why);
+        let (divided_name, candidates) = if self.candidates > 0 {
+            // The most probable candidate is the divided name, so the name is divided once.
+            let mut candidates = divider
+                .try_divide_name_candidates(undivided_name)
+                .map_err(divide_error)?;
+            candidates.truncate(self.candidates);
+            let output_candidates = candidates
+                .iter()
+                .map(|candidate| OutputCandidate {
+                    family: candidate.family.clone(),
+                    given: candidate.given.clone(),
+                    score: candidate.score,
+                })
+                .collect::<Vec<OutputCandidate>>();
+            let divided_name = match candidates.into_iter().next() {
+                Some(divided_name) => divided_name,
+                None => divider
+                    .try_divide_name(undivided_name)
+                    .map_err(divide_error)?,
+            };
+            (divided_name, Some(output_candidates))
+        } else {
+            let divided_name = divider
+                .try_divide_name(undivided_name)
+                .map_err(divide_error)?;
+            (divided_name, None)
+        };
+        let mut warnings = encodability_messages(encoding_check, &divided_name);
+        if self.warnings {
+            warnings.extend(divided_name_warnings(&divided_name));
+        }
+        Ok(match self.format {
+            OutputFormat::Text => (
+                format!(
+                    "{}{}{}",
+                    divided_name.family, divided_name.separator, divided_name.given
+                ),
+                warnings,
+            ),
+            OutputFormat::Tsv => {
+                let mut fields = vec![
+                    divided_name.family.clone(),
+                    divided_name.given.clone(),
+                    divided_name.score.to_string(),
+                    divided_name.algorithm(),
+                    divided_name.provenance.kind().to_string(),
+                    divided_name.normalized_family.clone(),
+                    divided_name.normalized_given.clone(),
+                ];
+                if let Some(candidates) = &candidates {
+                    fields.push(
+                        candidates
+                            .iter()
+                            .map(|candidate| {
+                                format!(
+                                    "{}{}{}:{}",
+                                    candidate.family,
+                                    divided_name.separator,
+                                    candidate.given,
+                                    candidate.score
+                                )
+                            })
+                            .collect::<Vec<String>>()
+                            .join("|"),
+                    );
+                }
+                (fields.join("\t"), warnings)
+            }
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                let output_divided_name = OutputDividedName {
+                    algorithm: divided_name.algorithm(),
+                    family: divided_name.family,
+                    given: divided_name.given,
+                    separator: divided_name.separator,
+                    score: divided_name.score,
+                    provenance: divided_name.provenance,
+                    normalized_family: divided_name.normalized_family,
+                    normalized_given: divided_name.normalized_given,
+                    family_span: divided_name.family_span,
+                    given_span: divided_name.given_span,
+                    candidates,
+                    warnings,
+                };
+                let json = if pretty {
+                    serde_json::to_string_pretty(&output_divided_name)
+                } else {
+                    serde_json::to_string(&output_divided_name)
+                };
+                (json.unwrap(), vec![])
+            }
+        })
     }
 }
 
-fn read_file(file_path: &String) -> String {
-    let path = Path::new(file_path);
-    let display = path.display();
-    let mut file = match File::open(file_path) {
-        Err(why) => panic!("couldn't open {}: {}", display, why),
-        Ok(file) => file,
+/// Lays out the lines of divided names in the output format of a file: the header or
+/// the brackets of a JSON array around them, and the commas between the elements.
+struct OutputLines {
+    is_json_array: bool,
+    /// The last element of a JSON array, held back as it must not end with a comma.
+    last_element: Option<String>,
+}
+
+impl OutputLines {
+    /// Returns the lines to write before the divided names.
+    fn begin(output_options: &OutputOptions) -> (Self, Vec<String>) {
+        let is_json_array = output_options.format == OutputFormat::Json;
+        let mut lines: Vec<String> = output_options.header().into_iter().collect();
+        if is_json_array {
+            lines.push("[".to_string());
+        }
+        let output_lines = Self {
+            is_json_array,
+            last_element: None,
+        };
+        (output_lines, lines)
+    }
+
+    /// Returns the line to write now for the output of one name, if any.
+    fn add(&mut self, line: String) -> Option<String> {
+        if self.is_json_array {
+            self.last_element.replace(line).map(|element| element + ",")
+        } else {
+            Some(line)
+        }
+    }
+
+    /// Returns the lines to write after the divided names.
+    fn end(self) -> Vec<String> {
+        if self.is_json_array {
+            self.last_element
+                .into_iter()
+                .chain(["]".to_string()])
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// Warnings on how `divided_name` was divided, apart from encodability.
+fn divided_name_warnings(divided_name: &DividedName) -> Vec<String> {
+    let full_name = format!(
+        "{}{}{}",
+        divided_name.family, divided_name.separator, divided_name.given
+    );
+    let mut warnings = vec![];
+    if divided_name.provenance == Provenance::Fallback {
+        warnings.push(format!(
+            "{}: no usable score, divided at the highest scoring split with score 0",
+            full_name
+        ));
+    }
+    for c in divided_name
+        .normalized_family
+        .chars()
+        .chain(divided_name.normalized_given.chars())
+        .filter(|c| is_private_use(*c))
+    {
+        warnings.push(format!(
+            "{}: gaiji U+{:04X} has no mapping and was scored as an unknown char",
+            full_name, c as u32
+        ));
+    }
+    warnings
+}
+
+#[derive(Args)]
+struct EncodingCheckOptions {
+    /// Report chars of the divided names that cp932 or jisx0208 cannot represent, on stderr
+    #[clap(long)]
+    check_encoding: Option<EncodingTarget>,
+    /// Extra substitutes for --check-encoding: per line, a char, a tab and its substitute
+    #[clap(long)]
+    variant_table: Option<String>,
+}
+
+impl EncodingCheckOptions {
+    fn load(&self) -> Result<Option<(EncodingTarget, VariantTable)>, CliError> {
+        let variant_table = match &self.variant_table {
+            Some(variant_table_path) => VariantTable::from_text(&read_file(variant_table_path)?)
+                .map_err(|why| {
+                    CliError::Io(format!("couldn't parse {}: {}", variant_table_path, why))
+                })?,
+            None => VariantTable::default(),
+        };
+        Ok(self.check_encoding.map(|target| (target, variant_table)))
+    }
+}
+
+/// Lines for stderr on the chars of `divided_name` that the target encoding cannot represent.
+fn encodability_messages(
+    encoding_check: &Option<(EncodingTarget, VariantTable)>,
+    divided_name: &DividedName,
+) -> Vec<String> {
+    let (target, variant_table) = match encoding_check {
+        Some(encoding_check) => encoding_check,
+        None => return vec![],
     };
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("couldn't read {}: {}", display, why),
-        Ok(_) => {}
+    let report = check_encodability(divided_name, *target, variant_table);
+    let mut messages = vec![];
+    for (part, unencodable_chars) in [("family", &report.family), ("given", &report.given)] {
+        for unencodable_char in unencodable_chars {
+            let suggestion = match unencodable_char.substitute.as_deref() {
+                Some("") => ", drop it".to_string(),
+                Some(substitute) => format!(", use {}", substitute),
+                None => "".to_string(),
+            };
+            messages.push(format!(
+                "{}{}{}: {} char {} {} is not in {}{}",
+                divided_name.family,
+                divided_name.separator,
+                divided_name.given,
+                part,
+                unencodable_char.char_index,
+                unencodable_char.code_point,
+                target,
+                suggestion
+            ));
+        }
     }
-    s
+    messages
+}
+
+#[derive(Args)]
+struct TextEncodingOptions {
+    /// Encoding of name list files
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        ignore_case = true,
+        default_value_t = TextEncoding::Utf8
+    )]
+    input_encoding: TextEncoding,
+    /// Encoding of divided names written to stdout
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        ignore_case = true,
+        default_value_t = TextEncoding::Utf8
+    )]
+    output_encoding: TextEncoding,
+    /// On bytes invalid in the input encoding or chars the output encoding cannot represent
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        ignore_case = true,
+        default_value_t = InvalidTextPolicy::Strict
+    )]
+    encoding_errors: InvalidTextPolicy,
 }
 
 #[derive(Parser)]
+#[clap(
+    after_help = "Exit status: 0 on success, 2 on bad arguments or options, 3 when a file \
+couldn't be read, parsed or written, 4 when some names couldn't be divided (the others \
+are still written)"
+)]
 struct AppArg {
     #[clap(subcommand)]
     action: Action,
+    #[clap(flatten)]
+    text_encoding_options: TextEncodingOptions,
 }
 
 #[derive(Subcommand)]
 enum Action {
     Name {
         undivided_name: String,
-        #[clap(default_value = "basic")]
-        mode: String,
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        #[clap(flatten)]
+        encoding_check_options: EncodingCheckOptions,
+        #[clap(flatten)]
+        output_options: OutputOptions,
     },
+    /// Divide the names of a file, one per line, as a stream
     File {
+        /// Name list, or `-` for stdin
         undivided_name_text: String,
-        #[clap(default_value = "basic")]
-        mode: String,
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        #[clap(flatten)]
+        encoding_check_options: EncodingCheckOptions,
+        #[clap(flatten)]
+        output_options: OutputOptions,
+        #[clap(flatten)]
+        worker_options: WorkerOptions,
     },
+    /// Divide a name column of a CSV or TSV file, appending the family and the given name
+    Csv {
+        /// CSV file, or `-` for stdin
+        input: String,
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        /// Column of the undivided names: a header field, or a 0-based index with --no-header
+        #[clap(long)]
+        column: String,
+        /// The first record is data, not a header; --column must then be an index
+        #[clap(long)]
+        no_header: bool,
+        /// Field delimiter: a single ASCII char, or `tab` for TSV
+        #[clap(long, default_value = ",")]
+        delimiter: String,
+        #[clap(long, default_value = "family")]
+        family_column: String,
+        #[clap(long, default_value = "given")]
+        given_column: String,
+        #[clap(long, default_value = "score")]
+        score_column: String,
+        #[clap(long, default_value = "algorithm")]
+        algorithm_column: String,
+        #[clap(flatten)]
+        options: DividerOptions,
+        #[clap(flatten)]
+        worker_options: WorkerOptions,
+    },
+    /// Evaluate a mode on a divided name corpus: mismatches, then accuracy by length,
+    /// provenance, score threshold and rank
     Accuracy {
         divided_name_text: String,
-        #[clap(default_value = "basic")]
-        mode: String,
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        /// Report how often the gold split is within the top 1..=k candidates
+        #[clap(long, default_value_t = 3)]
+        top_k: usize,
+        /// Score thresholds of the coverage curve
+        #[clap(
+            long,
+            value_delimiter = ',',
+            default_value = "0.5,0.6,0.7,0.8,0.9,0.95,0.99"
+        )]
+        thresholds: Vec<f64>,
+        /// Number of equal-width score bins for the calibration
+        #[clap(long, default_value_t = 10)]
+        calibration_bins: usize,
+        /// Write the report, including the mismatches, as JSON
+        #[clap(long)]
+        json: bool,
+        #[clap(flatten)]
+        worker_options: WorkerOptions,
+    },
+    /// Load a divider once and divide names typed one per line, with `:` commands to
+    /// switch the mode and show candidates and explanations
+    Repl {
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        /// History file (default: ~/.namedivider_history)
+        #[clap(long)]
+        history: Option<String>,
+    },
+    /// Go through the names divided with a score below a threshold one by one, and save
+    /// the splits accepted, picked or typed in to a user dictionary
+    Review {
+        /// Names, one per line
+        input: String,
+        #[clap(value_enum, default_value_t = Mode::Basic)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        /// Review the names whose best split scores below this
+        #[clap(long, default_value_t = 0.8)]
+        threshold: f64,
+        /// Number of candidates shown for each name
+        #[clap(long, default_value_t = 3)]
+        candidates: usize,
+        /// User dictionary the decisions are appended to, and read on start so that
+        /// names already decided are not asked again
+        #[clap(long)]
+        dictionary: String,
+        /// Write every name divided, with the decisions applied, here
+        #[clap(long)]
+        output: Option<String>,
+    },
+    /// Run two dividers on the same names and list the names they divide differently
+    Compare {
+        /// Names, one per line, or `-` for stdin; a divided name corpus with --gold
+        input: String,
+        /// First divider: a mode, then comma-separated options such as
+        /// `gbdt,gbdt-model=model_v2.txt` (weights, model, assets, gbdt-model, gaiji,
+        /// user-dictionary, separator, no-normalize, only-order-score-when-4)
+        divider_a: String,
+        /// Second divider, in the same form as the first
+        divider_b: String,
+        /// The input is divided gold names; also score both dividers against them
+        #[clap(long)]
+        gold: bool,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        /// Write the comparison as JSON
+        #[clap(long)]
+        json: bool,
+        #[clap(flatten)]
+        worker_options: WorkerOptions,
+    },
+    /// Divide the names of a fixture of namedivider-python's divisions and report where the
+    /// splits and scores differ from it
+    Parity {
+        /// JSONL with a `family`, `given` and `score`, and optionally the undivided `name`
+        /// and the `algorithm`, per line, such as Python's `DividedName.to_dict()`
+        fixture: String,
+        #[clap(value_enum, default_value_t = Mode::Gbdt)]
+        mode: Mode,
+        #[clap(flatten)]
+        options: DividerOptions,
+        /// Largest score difference from the reference counted as the same score
+        #[clap(long, default_value_t = 0.001)]
+        tolerance: f64,
+        /// Write the report, including the differences, as JSON
+        #[clap(long)]
+        json: bool,
+        #[clap(flatten)]
+        worker_options: WorkerOptions,
+    },
+    /// Fit the basic mode's order/length weights on a divided name corpus, starting from
+    /// --weights if given
+    FitWeights {
+        divided_name_text: String,
+        output: String,
+        #[clap(flatten)]
+        options: DividerOptions,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+    },
+    /// Train a logistic regression model on a divided name corpus and report its accuracy
+    /// on held-out names
+    TrainLogistic {
+        divided_name_text: String,
+        output: String,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        #[clap(long, default_value_t = 300)]
+        epochs: usize,
+        #[clap(long, default_value_t = 0.5)]
+        learning_rate: f64,
+        #[clap(long, default_value_t = 0.0001)]
+        l2: f64,
+        /// Use only the GBDT features, without the extra ones
+        #[clap(long)]
+        no_extra_features: bool,
+        /// Share of the names held out for validation
+        #[clap(long, default_value_t = 0.1)]
+        validation_ratio: f64,
+        /// Seed choosing the validation names
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+        #[clap(flatten)]
+        options: DividerOptions,
+    },
+    /// Train a LightGBM model for the gbdt mode on a divided name corpus and report its
+    /// accuracy on held-out names
+    TrainGbdt {
+        divided_name_text: String,
+        /// Model text, for `--gbdt-model` or `compile-assets --gbdt-model`
+        output: String,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        #[clap(long, default_value_t = 300)]
+        num_iterations: usize,
+        #[clap(long, default_value_t = 0.1)]
+        learning_rate: f64,
+        #[clap(long, default_value_t = 31)]
+        num_leaves: usize,
+        #[clap(long, default_value_t = 20)]
+        min_data_in_leaf: usize,
+        /// Further LightGBM parameters as a JSON object, such as `{"lambda_l2": 1.0}`
+        #[clap(long)]
+        params: Option<String>,
+        /// Share of the names held out for validation
+        #[clap(long, default_value_t = 0.1)]
+        validation_ratio: f64,
+        /// Seed choosing the validation names
+        #[clap(long, default_value_t = 0)]
+        seed: u64,
+        #[clap(flatten)]
+        options: DividerOptions,
+    },
+    /// Build character n-gram boundary statistics from a divided name corpus
+    BuildNgram {
+        divided_name_text: String,
+        output: String,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        /// Drop n-grams seen fewer times than this
+        #[clap(long, default_value_t = 1)]
+        min_count: u32,
+    },
+    /// Count the order and length status of each char of a divided name corpus and write
+    /// them as kanji.json, for the basic mode and `compile-assets --kanji`
+    BuildStats {
+        divided_name_text: String,
+        output: String,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        /// Drop chars seen fewer times than this
+        #[clap(long, default_value_t = 1)]
+        min_count: u32,
+    },
+    /// Show the kanji statistics of each char of a name and the rank of each prefix in
+    /// the family name list
+    Lookup {
+        name: String,
+        /// Assets written by `compile-assets` instead of the embedded ones
+        #[clap(long)]
+        assets: Option<String>,
+        /// Look up the chars as written, without replacing old glyph forms such as 髙
+        #[clap(long)]
+        no_normalize: bool,
+        #[clap(long)]
+        json: bool,
+    },
+    /// Write the GBDT features of every split candidate of a divided name corpus, labelled
+    /// 1 for the gold split, for training a model
+    ExportFeatures {
+        divided_name_text: String,
+        output: String,
+        #[clap(flatten)]
+        gold_file_options: GoldFileOptions,
+        /// csv or tsv with a header, or libsvm with the group sizes in `<output>.query`
+        #[clap(long, value_enum, default_value_t = FeatureFormat::Csv)]
+        format: FeatureFormat,
+        #[clap(flatten)]
+        options: DividerOptions,
+    },
+    /// Pack kanji statistics, family names and the GBDT model into one file for fast startup
+    CompileAssets {
+        output: String,
+        /// Kanji statistics in the format of kanji.json (default: the embedded one)
+        #[clap(long)]
+        kanji: Option<String>,
+        /// Family names, one per line, most common first (default: the embedded one)
+        #[clap(long)]
+        family_names: Option<String>,
+        /// LightGBM model text (default: the embedded one)
+        #[clap(long)]
+        gbdt_model: Option<String>,
+        /// Version reported in the provenance (default: the embedded model's, or the
+        /// file name of --gbdt-model)
+        #[clap(long)]
+        gbdt_model_version: Option<String>,
     },
 }
 
 fn main() {
     let cli = AppArg::parse();
+    if let Err(why) = run(cli) {
+        eprintln!("error: {}", why);
+        std::process::exit(why.exit_code());
+    }
+}
+
+fn run(cli: AppArg) -> Result<(), CliError> {
+    let text_encoding_options = cli.text_encoding_options;
     match cli.action {
         Action::Name {
             undivided_name,
             mode,
+            options,
+            encoding_check_options,
+            output_options,
         } => {
-            let divider = create_divider(&mode);
-            let divided_name = divide_name(&divider, &undivided_name);
-            println!(
-                "{}{}{}",
-                divided_name.family, divided_name.separator, divided_name.given
-            );
+            let divider = create_divider(mode, &options)?;
+            let encoding_check = encoding_check_options.load()?;
+            // A single name is written as a readable object rather than in an array.
+            let (line, warnings) = output_options
+                .divide(
+                    divider.as_ref(),
+                    &undivided_name,
+                    &encoding_check,
+                    output_options.format == OutputFormat::Json,
+                )
+                .map_err(CliError::Divide)?;
+            let mut output = TextOutput::new(&text_encoding_options);
+            if let Some(header) = output_options.header() {
+                output.print_line(&header);
+            }
+            output.print_line(&line);
+            output.finish()?;
+            for warning in warnings {
+                eprintln!("{}", warning);
+            }
         }
         Action::File {
             undivided_name_text,
             mode,
+            options,
+            encoding_check_options,
+            output_options,
+            worker_options,
         } => {
-            let file_contents = read_file(&undivided_name_text);
-            let divider = create_divider(&mode);
-            for undivided_name in file_contents.lines() {
-                let divided_name = divide_name(&divider, &undivided_name.to_string());
-                println!(
-                    "{}{}{}",
-                    divided_name.family, divided_name.separator, divided_name.given
-                );
+            // Built before any name is read, so that bad options are reported first.
+            let divider = create_divider(mode, &options)?;
+            let encoding_check = encoding_check_options.load()?;
+            let read_error = Mutex::new(None);
+            let batches =
+                read_line_batches(&undivided_name_text, &text_encoding_options, &read_error)?;
+            let mut output = TextOutput::new(&text_encoding_options);
+            let (mut output_lines, lines) = OutputLines::begin(&output_options);
+            for line in lines {
+                output.print_line(&line);
             }
+            let mut progress = Progress::new(worker_options.progress);
+            let mut num_failed = 0;
+            process_in_order(
+                batches,
+                worker_options.num_workers(),
+                &divider,
+                |divider, undivided_name| {
+                    if undivided_name.trim().is_empty() {
+                        return Ok(None);
+                    }
+                    output_options
+                        .divide(divider.as_ref(), &undivided_name, &encoding_check, false)
+                        .map(Some)
+                },
+                |results| {
+                    let num_names = results.len();
+                    for result in results {
+                        let (line, warnings) = match result {
+                            Ok(Some(divided)) => divided,
+                            // Blank lines stay blank, without counting as a failure.
+                            Ok(None) => (output_options.placeholder(), vec![]),
+                            Err(why) => {
+                                num_failed += 1;
+                                (output_options.placeholder(), vec![why])
+                            }
+                        };
+                        if let Some(line) = output_lines.add(line) {
+                            output.print_line(&line);
+                        }
+                        for warning in warnings {
+                            eprintln!("{}", warning);
+                        }
+                    }
+                    output.flush();
+                    progress.add(num_names);
+                },
+            );
+            for line in output_lines.end() {
+                output.print_line(&line);
+            }
+            output.finish()?;
+            progress.finish();
+            take_read_error(read_error)?;
+            check_num_failed(num_failed)?;
+        }
+        Action::Csv {
+            input,
+            mode,
+            column,
+            no_header,
+            delimiter,
+            family_column,
+            given_column,
+            score_column,
+            algorithm_column,
+            options,
+            worker_options,
+        } => {
+            let delimiter = parse_delimiter(&delimiter)?;
+            let divider = create_divider(mode, &options)?;
+            let input_encoding = text_encoding_options.input_encoding;
+            let encoding_errors = text_encoding_options.encoding_errors;
+            let input_path = input.clone();
+            let read_error = Mutex::new(None);
+            let mut records = csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(open_input(&input)?)
+                .into_byte_records()
+                .map(move |record| {
+                    let record = record.map_err(|why| {
+                        CliError::Io(format!("couldn't read {}: {}", input_path, why))
+                    })?;
+                    let line = record.position().map_or(0, |position| position.line());
+                    record
+                        .iter()
+                        .enumerate()
+                        .map(|(column_idx, field)| {
+                            decode_text(field, input_encoding, encoding_errors).map_err(|_| {
+                                CliError::Io(format!(
+                                    "couldn't decode {}: invalid {} byte sequence at line {}, column {}",
+                                    input_path, input_encoding, line, column_idx
+                                ))
+                            })
+                        })
+                        .collect::<Result<Vec<String>, CliError>>()
+                })
+                .map_while(|record| match record {
+                    Ok(record) => Some(record),
+                    Err(why) => {
+                        *read_error.lock().unwrap() = Some(why);
+                        None
+                    }
+                });
+
+            let mut output = TextOutput::new(&text_encoding_options);
+            let mut formatter = CsvRecordFormatter::new(delimiter);
+            let name_column = if no_header {
+                CsvNameColumn::find(None, &column, &input)?
+            } else {
+                let mut header = records.next().unwrap_or_default();
+                if let Some(why) = read_error.lock().unwrap().take() {
+                    return Err(why);
+                }
+                let name_column = CsvNameColumn::find(Some(&header), &column, &input)?;
+                header.extend([family_column, given_column, score_column, algorithm_column]);
+                output.print_line(&formatter.format(&header));
+                name_column
+            };
+            let batches = std::iter::from_fn(move || {
+                let batch: Vec<Vec<String>> = records.by_ref().take(BATCH_SIZE).collect();
+                if batch.is_empty() {
+                    None
+                } else {
+                    Some(batch)
+                }
+            });
+
+            let mut progress = Progress::new(worker_options.progress);
+            let mut num_failed = 0;
+            process_in_order(
+                batches,
+                worker_options.num_workers(),
+                &divider,
+                |divider, fields| name_column.divide(divider.as_ref(), fields),
+                |records| {
+                    for (fields, failure) in &records {
+                        output.print_line(&formatter.format(fields));
+                        if let Some(why) = failure {
+                            num_failed += 1;
+                            eprintln!("{}", why);
+                        }
+                    }
+                    output.flush();
+                    progress.add(records.len());
+                },
+            );
+            output.finish()?;
+            progress.finish();
+            take_read_error(read_error)?;
+            check_num_failed(num_failed)?;
         }
         Action::Accuracy {
             divided_name_text,
             mode,
+            options,
+            gold_file_options,
+            top_k,
+            thresholds,
+            calibration_bins,
+            json,
+            worker_options,
+        } => {
+            gold_file_options.check()?;
+            let divider = create_divider(mode, &options)?;
+            let read_error = Mutex::new(None);
+            let batches =
+                read_line_batches(&divided_name_text, &text_encoding_options, &read_error)?;
+            let mut builder = AccuracyReportBuilder::new(top_k, thresholds, calibration_bins);
+            let mut mismatches = vec![];
+            let mut num_skipped_lines = 0;
+            let mut progress = Progress::new(worker_options.progress);
+            process_in_order(
+                batches,
+                worker_options.num_workers(),
+                &divider,
+                |divider, line| {
+                    gold_file_options
+                        .parse_line(&line)
+                        .map(|gold_name| (line, evaluate_name(divider.as_ref(), gold_name, top_k)))
+                },
+                |results| {
+                    progress.add(results.len());
+                    for result in results {
+                        let (line, name_evaluation) = match result {
+                            Some(result) => result,
+                            None => {
+                                num_skipped_lines += 1;
+                                continue;
+                            }
+                        };
+                        builder.add(&name_evaluation);
+                        if !name_evaluation.is_correct() {
+                            let divided_name = &name_evaluation.divided_name;
+                            mismatches.push(Mismatch {
+                                gold: line,
+                                divided: format!(
+                                    "{}{}{}",
+                                    divided_name.family, divided_name.separator, divided_name.given
+                                ),
+                                score: divided_name.score,
+                            });
+                        }
+                    }
+                },
+            );
+            progress.finish();
+            let report = builder.build();
+            let mut output = TextOutput::new(&text_encoding_options);
+            if json {
+                let json_report = JsonAccuracyReport {
+                    report,
+                    num_skipped_lines,
+                    mismatches,
+                };
+                output.print_line(&serde_json::to_string_pretty(&json_report).unwrap());
+            } else {
+                for mismatch in &mismatches {
+                    output.print_line(&format!("{}, {}", mismatch.gold, mismatch.divided));
+                }
+                for line in format_accuracy_report(&report, num_skipped_lines) {
+                    output.print_line(&line);
+                }
+            }
+            output.finish()?;
+            take_read_error(read_error)?;
+        }
+        Action::Repl {
+            mode,
+            options,
+            history,
+        } => {
+            let history = history.or_else(|| {
+                std::env::var_os("HOME").map(|home| {
+                    Path::new(&home)
+                        .join(".namedivider_history")
+                        .to_string_lossy()
+                        .to_string()
+                })
+            });
+            let mut repl = Repl::new(mode, options)?;
+            println!("mode: {}", repl.mode);
+            let mut editor = DefaultEditor::new()
+                .map_err(|why| CliError::Io(format!("couldn't start the line editor: {}", why)))?;
+            if let Some(history) = &history {
+                // A missing history file is just an empty history.
+                let _ = editor.load_history(history);
+            }
+            loop {
+                let line = match editor.readline(&format!("{}> ", repl.mode)) {
+                    Ok(line) => line,
+                    Err(ReadlineError::Interrupted) => continue,
+                    Err(ReadlineError::Eof) => break,
+                    Err(why) => return Err(CliError::Io(format!("couldn't read a line: {}", why))),
+                };
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(line);
+                if REPL_QUIT_COMMANDS.contains(&line) {
+                    break;
+                }
+                let output = if line.starts_with(':') {
+                    repl.run_command(line)
+                } else {
+                    repl.divide(line)
+                };
+                for line in output {
+                    println!("{}", line);
+                }
+            }
+            if let Some(history) = &history {
+                if let Err(why) = editor.save_history(history) {
+                    eprintln!("couldn't write {}: {}", history, why);
+                }
+            }
+        }
+        Action::Review {
+            input,
+            mode,
+            mut options,
+            threshold,
+            candidates: num_candidates,
+            dictionary,
+            output,
+        } => {
+            if options.user_dictionary.is_some() {
+                return Err(CliError::Usage(
+                    "review reads its user dictionary from --dictionary".to_string(),
+                ));
+            }
+            // Names in the dictionary are divided as decided with a score of 1.0, so a
+            // resumed review skips them.
+            if Path::new(&dictionary).exists() {
+                options.user_dictionary = Some(dictionary.clone());
+            }
+            let separator = options.separator.clone();
+            let divider = create_divider(mode, &options)?;
+            let names: Vec<String> = read_names_file(&input, &text_encoding_options)?
+                .lines()
+                .map(|line| line.trim().to_string())
+                .collect();
+            let mut divided_names: Vec<Option<Vec<DividedName>>> = names
+                .iter()
+                .map(|name| divider.try_divide_name_candidates(name).ok())
+                .collect();
+            let under_threshold: Vec<usize> = (0..names.len())
+                .filter(|&i| match &divided_names[i] {
+                    Some(candidates) => candidates[0].score < threshold,
+                    None => false,
+                })
+                .collect();
+            let mut dictionary_file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&dictionary)
+                .map_err(|why| CliError::Io(format!("couldn't open {}: {}", dictionary, why)))?;
+            let mut editor = DefaultEditor::new()
+                .map_err(|why| CliError::Io(format!("couldn't start the line editor: {}", why)))?;
+            eprintln!(
+                "{} of {} names score below {}. {}",
+                under_threshold.len(),
+                names.len(),
+                threshold,
+                REVIEW_HELP
+            );
+            // Decisions of this session by name, so that a repeated name is asked once.
+            let mut decisions: HashMap<String, (String, String)> = HashMap::new();
+            let (mut num_accepted, mut num_picked, mut num_corrected, mut num_skipped) =
+                (0, 0, 0, 0);
+            let mut num_left = under_threshold.len();
+            'names: for (progress, &i) in under_threshold.iter().enumerate() {
+                let name = &names[i];
+                if decisions.contains_key(name) {
+                    num_left -= 1;
+                    continue;
+                }
+                let candidates = divided_names[i].as_ref().unwrap();
+                eprintln!("[{}/{}] {}", progress + 1, under_threshold.len(), name);
+                for (rank, candidate) in candidates.iter().take(num_candidates).enumerate() {
+                    eprintln!(
+                        "  {}) {}{}{}\t{:.4}\t{}",
+                        rank + 1,
+                        candidate.family,
+                        separator,
+                        candidate.given,
+                        candidate.score,
+                        candidate.algorithm()
+                    );
+                }
+                let shown = candidates.len().min(num_candidates);
+                let (family, given) = loop {
+                    let answer = match editor.readline("> ") {
+                        Ok(answer) => answer,
+                        Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break 'names,
+                        Err(why) => {
+                            return Err(CliError::Io(format!("couldn't read a line: {}", why)))
+                        }
+                    };
+                    match parse_review_answer(&answer, name, shown) {
+                        Ok(ReviewAnswer::Pick(rank)) => {
+                            if rank == 0 {
+                                num_accepted += 1;
+                            } else {
+                                num_picked += 1;
+                            }
+                            break (
+                                candidates[rank].family.clone(),
+                                candidates[rank].given.clone(),
+                            );
+                        }
+                        Ok(ReviewAnswer::Correct(family, given)) => {
+                            num_corrected += 1;
+                            break (family, given);
+                        }
+                        Ok(ReviewAnswer::Skip) => {
+                            num_skipped += 1;
+                            num_left -= 1;
+                            continue 'names;
+                        }
+                        Ok(ReviewAnswer::Quit) => break 'names,
+                        Err(why) => eprintln!("{} {}", why, REVIEW_HELP),
+                    }
+                };
+                // Appended at once, so that the decisions survive an interrupted review.
+                writeln!(dictionary_file, "{} {}", family, given).map_err(|why| {
+                    CliError::Io(format!("couldn't write {}: {}", dictionary, why))
+                })?;
+                decisions.insert(name.clone(), (family, given));
+                num_left -= 1;
+            }
+            eprintln!(
+                "accepted {}, picked {}, corrected {}, skipped {}, left {}",
+                num_accepted, num_picked, num_corrected, num_skipped, num_left
+            );
+            if let Some(output) = output {
+                let mut text = String::new();
+                for (name, divided_name) in names.iter().zip(divided_names.iter_mut()) {
+                    let line = match (decisions.get(name), divided_name.take()) {
+                        (Some((family, given)), _) => format!("{}{}{}", family, separator, given),
+                        (None, Some(candidates)) => format!(
+                            "{}{}{}",
+                            candidates[0].family, separator, candidates[0].given
+                        ),
+                        (None, None) => name.clone(),
+                    };
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+                let mut bytes = byte_order_mark(text_encoding_options.output_encoding).to_vec();
+                bytes.extend(
+                    encode_text(
+                        &text,
+                        text_encoding_options.output_encoding,
+                        text_encoding_options.encoding_errors,
+                    )
+                    .map_err(|why| CliError::Io(format!("couldn't encode {}: {}", output, why)))?,
+                );
+                std::fs::write(&output, bytes)
+                    .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            }
+        }
+        Action::Compare {
+            input,
+            divider_a,
+            divider_b,
+            gold,
+            gold_file_options,
+            json,
+            worker_options,
+        } => {
+            gold_file_options.check()?;
+            let (mode_a, options_a) = parse_divider_spec(&divider_a)?;
+            let (mode_b, options_b) = parse_divider_spec(&divider_b)?;
+            let dividers = (
+                create_divider(mode_a, &options_a)?,
+                create_divider(mode_b, &options_b)?,
+            );
+            let read_error = Mutex::new(None);
+            let batches = read_line_batches(&input, &text_encoding_options, &read_error)?;
+            let mut comparison = Comparison {
+                divider_a: divider_a.clone(),
+                divider_b: divider_b.clone(),
+                num_names: 0,
+                num_agreed: 0,
+                agreement: 0.0,
+                gold: if gold {
+                    Some(GoldComparison::default())
+                } else {
+                    None
+                },
+                differences: vec![],
+                gold_separator: gold_file_options.gold_separator.clone(),
+            };
+            let mut num_skipped_lines = 0;
+            let mut progress = Progress::new(worker_options.progress);
+            process_in_order(
+                batches,
+                worker_options.num_workers(),
+                &dividers,
+                |(divider_a, divider_b), line| {
+                    let (undivided_name, gold_name) = if gold {
+                        let gold_name = gold_file_options.parse_line(&line)?;
+                        (gold_name.undivided_name(), Some(gold_name))
+                    } else {
+                        (line.trim().to_string(), None)
+                    };
+                    let divided_name_a = divider_a.try_divide_name(&undivided_name).ok()?;
+                    let divided_name_b = divider_b.try_divide_name(&undivided_name).ok()?;
+                    Some((undivided_name, divided_name_a, divided_name_b, gold_name))
+                },
+                |results| {
+                    progress.add(results.len());
+                    for result in results {
+                        match result {
+                            Some((undivided_name, divided_name_a, divided_name_b, gold_name)) => {
+                                comparison.add(
+                                    undivided_name,
+                                    divided_name_a,
+                                    divided_name_b,
+                                    gold_name,
+                                )
+                            }
+                            None => num_skipped_lines += 1,
+                        }
+                    }
+                },
+            );
+            progress.finish();
+            if num_skipped_lines > 0 {
+                eprintln!(
+                    "skipped {} lines without a name to divide",
+                    num_skipped_lines
+                );
+            }
+            let mut output = TextOutput::new(&text_encoding_options);
+            if json {
+                output.print_line(&serde_json::to_string_pretty(&comparison).unwrap());
+            } else {
+                for line in format_comparison(&comparison) {
+                    output.print_line(&line);
+                }
+            }
+            output.finish()?;
+            take_read_error(read_error)?;
+        }
+        Action::Parity {
+            fixture,
+            mode,
+            options,
+            tolerance,
+            json,
+            worker_options,
+        } => {
+            let divider = create_divider(mode, &options)?;
+            let read_error = Mutex::new(None);
+            let batches = read_line_batches(&fixture, &text_encoding_options, &read_error)?;
+            let mut builder = ParityReportBuilder::new(tolerance);
+            let mut num_lines = 0;
+            let mut num_skipped_lines = 0;
+            let mut parse_error = None;
+            let mut progress = Progress::new(worker_options.progress);
+            process_in_order(
+                batches,
+                worker_options.num_workers(),
+                &divider,
+                |divider, line| -> Result<_, &'static str> {
+                    if line.trim().is_empty() {
+                        return Ok(None);
+                    }
+                    let reference = parse_reference_division(&line)?;
+                    let undivided_name = reference.undivided_name();
+                    Ok(divider
+                        .try_divide_name(&undivided_name)
+                        .ok()
+                        .map(|divided_name| (reference, divided_name)))
+                },
+                |results| {
+                    progress.add(results.len());
+                    for result in results {
+                        num_lines += 1;
+                        match result {
+                            Ok(Some((reference, divided_name))) => {
+                                builder.add(&reference, &divided_name)
+                            }
+                            Ok(None) => num_skipped_lines += 1,
+                            Err(why) => {
+                                if parse_error.is_none() {
+                                    parse_error = Some(CliError::Io(format!(
+                                        "couldn't parse line {} of {}: {}",
+                                        num_lines, fixture, why
+                                    )));
+                                }
+                            }
+                        }
+                    }
+                },
+            );
+            progress.finish();
+            take_read_error(read_error)?;
+            if let Some(why) = parse_error {
+                return Err(why);
+            }
+            let report = builder.build();
+            let mut output = TextOutput::new(&text_encoding_options);
+            if json {
+                let json_report = JsonParityReport {
+                    report,
+                    num_skipped_lines,
+                };
+                output.print_line(&serde_json::to_string_pretty(&json_report).unwrap());
+            } else {
+                for line in format_parity_report(&report, num_skipped_lines) {
+                    output.print_line(&line);
+                }
+            }
+            output.finish()?;
+        }
+        Action::FitWeights {
+            divided_name_text,
+            output,
+            options,
+            gold_file_options,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let compiled_assets = match &options.assets {
+                Some(assets_path) => Some(load_compiled_assets(assets_path)?),
+                None => None,
+            };
+            let mut divider = create_basic_divider(&options, compiled_assets.as_ref())?;
+            divider.name_divider_base.gaiji_mapping = load_gaiji_mapping(&options)?;
+            let fit = fit_basic_score_weights(&gold_names, &divider);
+            for bucket in &fit.buckets {
+                println!(
+                    "length {}: order_weight={} ({} names, {} -> {} correct)",
+                    bucket.label,
+                    bucket.order_weight,
+                    bucket.num_names,
+                    bucket.correct_before,
+                    bucket.correct_after
+                );
+            }
+            std::fs::write(&output, fit.weights.to_json())
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+        }
+        Action::TrainLogistic {
+            divided_name_text,
+            output,
+            gold_file_options,
+            epochs,
+            learning_rate,
+            l2,
+            no_extra_features,
+            validation_ratio,
+            seed,
+            options,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let config = LogisticTrainerConfig {
+                epochs,
+                learning_rate,
+                l2,
+                use_extra_features: !no_extra_features,
+            };
+            let feature_extractor = FamilyRankingFeatureExtractor::shared();
+            let mut name_divider_base = NameDividerBase::new(
+                options.separator.clone(),
+                !options.no_normalize,
+                Provenance::Model {
+                    name: "logistic".to_string(),
+                    version: None,
+                },
+            );
+            name_divider_base.gaiji_mapping = load_gaiji_mapping(&options)?;
+            let (training_names, validation_names) =
+                split_validation_names(gold_names, validation_ratio, seed);
+            let model = train_logistic_model(
+                &training_names,
+                &feature_extractor,
+                &name_divider_base,
+                &config,
+            );
+            std::fs::write(&output, model.to_json())
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+
+            let mut divider =
+                get_logistic_name_divider(options.separator.clone(), !options.no_normalize, model);
+            divider.name_divider_base.gaiji_mapping = name_divider_base.gaiji_mapping;
+            let num_correct = validation_names
+                .iter()
+                .filter(|gold_name| {
+                    divider
+                        .try_divide_name(&gold_name.undivided_name())
+                        .is_ok_and(|divided_name| divided_name.family == gold_name.family)
+                })
+                .count();
+            println!(
+                "trained on {} names, validation accuracy: {}",
+                training_names.len(),
+                format_accuracy(
+                    num_correct,
+                    validation_names.len(),
+                    num_correct as f64 / validation_names.len().max(1) as f64
+                )
+            );
+        }
+        Action::TrainGbdt {
+            divided_name_text,
+            output,
+            gold_file_options,
+            num_iterations,
+            learning_rate,
+            num_leaves,
+            min_data_in_leaf,
+            params,
+            validation_ratio,
+            seed,
+            options,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let extra_params = match &params {
+                Some(params) => serde_json::from_str(params).map_err(|why| {
+                    CliError::Usage(format!("couldn't parse --params {}: {}", params, why))
+                })?,
+                None => serde_json::Map::new(),
+            };
+            let config = GBDTTrainerConfig {
+                num_iterations,
+                learning_rate,
+                num_leaves,
+                min_data_in_leaf,
+                extra_params,
+            };
+            let (training_names, validation_names) =
+                split_validation_names(gold_names, validation_ratio, seed);
+            let feature_extractor = load_feature_extractor(&options.assets)?;
+            let name_divider_base = gbdt_divider_base(&options)?;
+            let booster = train_gbdt_model(
+                &training_names,
+                &feature_extractor,
+                &name_divider_base,
+                &config,
+            )
+            .map_err(|why| CliError::Io(format!("couldn't train the model: {}", why)))?;
+            booster
+                .save_file(&output)
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+
+            // Divide the held-out names with the written model, as `--gbdt-model` would.
+            let mut divider = get_gbdt_name_divider_with_model(
+                options.separator.clone(),
+                !options.no_normalize,
+                read_file(&output)?,
+                &model_version_from_path(&output),
+            )
+            .map_err(|why| CliError::Io(format!("couldn't use {}: {}", output, why)))?;
+            divider.gbdt_score_calculator.feature_extractor = feature_extractor;
+            divider.name_divider_base.gaiji_mapping = name_divider_base.gaiji_mapping;
+            let num_correct = validation_names
+                .iter()
+                .filter(|gold_name| {
+                    divider
+                        .try_divide_name(&gold_name.undivided_name())
+                        .is_ok_and(|divided_name| divided_name.family == gold_name.family)
+                })
+                .count();
+            println!(
+                "trained on {} names, validation accuracy: {}",
+                training_names.len(),
+                format_accuracy(
+                    num_correct,
+                    validation_names.len(),
+                    num_correct as f64 / validation_names.len().max(1) as f64
+                )
+            );
+        }
+        Action::BuildNgram {
+            divided_name_text,
+            output,
+            gold_file_options,
+            min_count,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let mut builder = NgramStatisticsBuilder::new();
+            for gold_name in &gold_names {
+                builder.add(
+                    &UndividedNameHolder::new(gold_name.family.clone()).normalized_name,
+                    &UndividedNameHolder::new(gold_name.given.clone()).normalized_name,
+                );
+            }
+            let ngram_statistics_repository = builder.build(min_count);
+            std::fs::write(&output, ngram_statistics_repository.to_bytes())
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            println!(
+                "{} n-grams from {} names",
+                ngram_statistics_repository.len(),
+                gold_names.len()
+            );
+        }
+        Action::BuildStats {
+            divided_name_text,
+            output,
+            gold_file_options,
+            min_count,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let mut builder = KanjiStatisticsBuilder::new();
+            for gold_name in &gold_names {
+                builder.add(
+                    &UndividedNameHolder::new(gold_name.family.clone()).normalized_name,
+                    &UndividedNameHolder::new(gold_name.given.clone()).normalized_name,
+                );
+            }
+            let kanji_statistics = builder.build(min_count);
+            std::fs::write(&output, to_kanji_json(&kanji_statistics))
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            println!(
+                "{} kanji from {} names",
+                kanji_statistics.len(),
+                gold_names.len()
+            );
+        }
+        Action::Lookup {
+            name,
+            assets,
+            no_normalize,
+            json,
         } => {
-            let file_contents = read_file(&divided_name_text);
-            let divider = create_divider(&mode);
-            let mut total: f64 = 0.0;
-            let mut ng: f64 = 0.0;
-            for divided_name in file_contents.lines() {
-                let divided_name_orig = divided_name.clone();
-                let undivided_name = divided_name.replace(' ', "");
-                let divided_name = divide_name(&divider, &undivided_name.to_string());
-                let divided_name_str =
-                    divided_name.family + &divided_name.separator + &divided_name.given;
-                total += 1.0;
-                if *divided_name_orig != divided_name_str {
-                    ng += 1.0;
-                    println!("{}, {}", divided_name_orig, divided_name_str)
+            let feature_extractor = load_feature_extractor(&assets)?;
+            let lookup = lookup_name(&name, !no_normalize, &feature_extractor);
+            let mut output = TextOutput::new(&text_encoding_options);
+            if json {
+                output.print_line(&serde_json::to_string_pretty(&lookup).unwrap());
+            } else {
+                for line in format_lookup(&lookup) {
+                    output.print_line(&line);
                 }
             }
-            println!("{}", (1.0 - (ng / total)));
+            output.finish()?;
+        }
+        Action::ExportFeatures {
+            divided_name_text,
+            output,
+            gold_file_options,
+            format,
+            options,
+        } => {
+            let gold_names = gold_file_options.read(&divided_name_text, &text_encoding_options)?;
+            let feature_extractor = load_feature_extractor(&options.assets)?;
+            let name_divider_base = gbdt_divider_base(&options)?;
+            let rows =
+                family_ranking_feature_rows(&gold_names, &feature_extractor, &name_divider_base);
+            let file = File::create(&output)
+                .map_err(|why| CliError::Io(format!("couldn't create {}: {}", output, why)))?;
+            let mut writer = BufWriter::new(file);
+            let mut lines = format_feature_rows(&rows, format);
+            if let Some(header) = format_feature_header(format) {
+                lines.insert(0, header);
+            }
+            for line in lines {
+                writeln!(writer, "{}", line)
+                    .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            }
+            writer
+                .flush()
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            let num_groups = rows.last().map_or(0, |row| row.group_id + 1);
+            if format == FeatureFormat::LibSvm {
+                let query_path = format!("{}.query", output);
+                let mut group_sizes = vec![0; num_groups];
+                for row in &rows {
+                    group_sizes[row.group_id] += 1;
+                }
+                let query: String = group_sizes
+                    .iter()
+                    .map(|group_size| format!("{}\n", group_size))
+                    .collect();
+                std::fs::write(&query_path, query).map_err(|why| {
+                    CliError::Io(format!("couldn't write {}: {}", query_path, why))
+                })?;
+            }
+            println!(
+                "{} candidates of {} names ({} divided by rule were skipped)",
+                rows.len(),
+                num_groups,
+                gold_names.len() - num_groups
+            );
+        }
+        Action::CompileAssets {
+            output,
+            kanji,
+            family_names,
+            gbdt_model,
+            gbdt_model_version,
+        } => {
+            let kanji_json = match &kanji {
+                Some(kanji_path) => read_file(kanji_path)?,
+                None => embedded_asset("kanji.json"),
+            };
+            let kanji_statistics = parse_kanji_statistics(&kanji_json).map_err(|why| {
+                CliError::Io(format!(
+                    "couldn't parse {}: {}",
+                    kanji.as_deref().unwrap_or("kanji.json"),
+                    why
+                ))
+            })?;
+            let family_names = match &family_names {
+                Some(family_names_path) => read_file(family_names_path)?,
+                None => embedded_asset("family_names.txt"),
+            };
+            let gbdt_model_version = gbdt_model_version.unwrap_or_else(|| match &gbdt_model {
+                Some(model_path) => model_version_from_path(model_path),
+                None => EMBEDDED_GBDT_MODEL_VERSION.to_string(),
+            });
+            let gbdt_model = match &gbdt_model {
+                Some(model_path) => read_file(model_path)?,
+                None => embedded_asset("gbdt_model_v1.txt"),
+            };
+            let bytes = compile_assets(
+                &kanji_statistics,
+                &family_names,
+                Some(&gbdt_model),
+                &gbdt_model_version,
+            )
+            .map_err(|why| CliError::Io(format!("couldn't compile assets: {}", why)))?;
+            std::fs::write(&output, &bytes)
+                .map_err(|why| CliError::Io(format!("couldn't write {}: {}", output, why)))?;
+            println!("{} bytes", bytes.len());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repl_options(args: &[&str]) -> DividerOptions {
+        let mut argv = vec!["namedivider-rs", "repl"];
+        argv.extend(args);
+        match AppArg::try_parse_from(argv).unwrap().action {
+            Action::Repl { options, .. } => options,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn repl_does_not_start_without_its_divider() {
+        let error = Repl::new(Mode::Logistic, repl_options(&[])).err().unwrap();
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn repl_keeps_its_mode_when_switching_fails() {
+        let mut repl = Repl::new(Mode::Basic, repl_options(&[])).unwrap();
+        let lines = repl.run_command(":mode logistic");
+        assert!(lines[0].starts_with("error: "));
+        assert_eq!(repl.mode, Mode::Basic);
+        assert!(repl.divide("菅義偉")[0].starts_with("菅 義偉\t"));
+
+        let lines = repl.run_command(":normalize off");
+        assert_eq!(lines, vec!["normalize: off".to_string()]);
+        assert!(repl.divide("菅義偉")[0].starts_with("菅 義偉\t"));
+    }
+
+    fn output_options(format: OutputFormat, candidates: usize) -> OutputOptions {
+        OutputOptions {
+            format,
+            candidates,
+            warnings: false,
+        }
+    }
+
+    /// The lines of a file output of `names`, where a name that can't be divided gets the
+    /// placeholder as in `Action::File`.
+    fn output_file(output_options: &OutputOptions, names: &[&str]) -> Vec<String> {
+        let divider = create_divider(Mode::Basic, &repl_options(&[])).unwrap();
+        let (mut output_lines, mut lines) = OutputLines::begin(output_options);
+        for name in names {
+            let line = match output_options.divide(&*divider, name, &None, false) {
+                Ok((line, _)) => line,
+                Err(_) => output_options.placeholder(),
+            };
+            lines.extend(output_lines.add(line));
+        }
+        lines.extend(output_lines.end());
+        lines
+    }
+
+    #[test]
+    fn tsv_header_matches_the_fields() {
+        let lines = output_file(&output_options(OutputFormat::Tsv, 0), &["菅義偉"]);
+        assert_eq!(lines[0], TSV_COLUMNS.join("\t"));
+        assert_eq!(lines[1].split('\t').count(), TSV_COLUMNS.len());
+        assert!(lines[1].starts_with("菅\t義偉\t"));
+
+        let lines = output_file(&output_options(OutputFormat::Tsv, 2), &["菅義偉"]);
+        assert_eq!(lines[0], TSV_COLUMNS.join("\t") + "\tcandidates");
+        assert_eq!(lines[1].split('\t').count(), TSV_COLUMNS.len() + 1);
+        assert_eq!(lines[1].rsplit('\t').next().unwrap().split('|').count(), 2);
+    }
+
+    #[test]
+    fn json_is_one_array_with_a_comma_between_elements() {
+        let options = output_options(OutputFormat::Json, 0);
+        assert_eq!(output_file(&options, &[]), vec!["[", "]"]);
+
+        let lines = output_file(&options, &["菅義偉"]);
+        assert_eq!(lines.len(), 3);
+        assert!(!lines[1].ends_with(','));
+
+        let lines = output_file(&options, &["菅義偉", "菅", "安倍晋三"]);
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].ends_with(','));
+        assert_eq!(lines[2], "null,");
+        assert!(!lines[3].ends_with(','));
+        let array: serde_json::Value = serde_json::from_str(&lines.join("\n")).unwrap();
+        let array = array.as_array().unwrap();
+        assert_eq!(array.len(), 3);
+        assert_eq!(array[0]["family"], "菅");
+        assert!(array[1].is_null());
+        assert_eq!(array[2]["given"], "晋三");
+    }
+
+    #[test]
+    fn jsonl_is_one_object_per_line() {
+        let lines = output_file(&output_options(OutputFormat::Jsonl, 0), &["菅義偉", "菅"]);
+        assert_eq!(lines.len(), 2);
+        let object: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(object["given"], "義偉");
+        assert!(object.get("candidates").is_none());
+        assert_eq!(lines[1], "null");
+    }
+
+    #[test]
+    fn undividable_name_is_a_divide_error() {
+        let divider = create_divider(Mode::Basic, &repl_options(&[])).unwrap();
+        for candidates in [0, 2] {
+            let options = output_options(OutputFormat::Text, candidates);
+            let why = options.divide(&*divider, "菅", &None, false).unwrap_err();
+            assert!(why.starts_with("couldn't divide \"菅\""));
+            assert!(options
+                .divide(&*divider, "\u{3099}菅", &None, false)
+                .is_ok());
+        }
+        let column = CsvNameColumn {
+            column_idx: 0,
+            num_input_columns: 1,
+        };
+        let (fields, failure) = column.divide(&*divider, vec!["菅".to_string()]);
+        assert_eq!(fields.len(), 5);
+        assert!(failure.is_some());
+        let (_, failure) = column.divide(&*divider, vec![String::new()]);
+        assert!(failure.is_none());
+    }
+
+    #[test]
+    fn divided_name_is_the_first_candidate() {
+        let lines = output_file(&output_options(OutputFormat::Jsonl, 3), &["安倍晋三"]);
+        let object: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        let candidates = object["candidates"].as_array().unwrap();
+        assert!(!candidates.is_empty() && candidates.len() <= 3);
+        assert_eq!(candidates[0]["family"], object["family"]);
+        assert_eq!(candidates[0]["given"], object["given"]);
+        assert_eq!(candidates[0]["score"], object["score"]);
+    }
+
+    #[test]
+    fn parse_divider_spec_reads_the_divider_flags() {
+        let (mode, options) =
+            parse_divider_spec("gbdt,gbdt-model=model.txt,separator=/,no-normalize").unwrap();
+        assert_eq!(mode, Mode::Gbdt);
+        assert_eq!(options.gbdt_model.as_deref(), Some("model.txt"));
+        assert_eq!(options.separator, "/");
+        assert!(options.no_normalize);
+        assert!(!options.only_order_score_when_4);
+
+        let (mode, options) = parse_divider_spec("basic").unwrap();
+        assert_eq!(mode, Mode::Basic);
+        assert_eq!(options.separator, " ");
+        assert!(options.weights.is_none());
+    }
+
+    #[test]
+    fn parse_divider_spec_rejects_unknown_options() {
+        for spec in [
+            "basic,unknown=1",
+            "basic,weights",
+            "unknown",
+            "basic,no-normalize=1",
+        ] {
+            let error = parse_divider_spec(spec).err().unwrap();
+            assert_eq!(error.exit_code(), EXIT_USAGE, "{}", spec);
+            assert!(!error.to_string().contains('\n'), "{}", spec);
+        }
+    }
+
+    fn gold_comparison(gold_separator: &str) -> Comparison {
+        Comparison {
+            divider_a: "basic".to_string(),
+            divider_b: "basic,no-normalize".to_string(),
+            num_names: 0,
+            num_agreed: 0,
+            agreement: 0.0,
+            gold: Some(GoldComparison::default()),
+            differences: vec![],
+            gold_separator: gold_separator.to_string(),
+        }
+    }
+
+    #[test]
+    fn comparison_counts_agreements_and_gold_matches() {
+        let divider = create_divider(Mode::Basic, &repl_options(&[])).unwrap();
+        let gold_name = |family: &str, given: &str| GoldName {
+            family: family.to_string(),
+            given: given.to_string(),
+        };
+        let mut comparison = gold_comparison("/");
+        let name = "安倍晋三".to_string();
+        comparison.add(
+            name.clone(),
+            divider.divide_name(&name),
+            divider.divide_name(&name),
+            Some(gold_name("安倍", "晋三")),
+        );
+        let other_split = divider.divide_name_candidates(&name).remove(1);
+        comparison.add(
+            name.clone(),
+            divider.divide_name(&name),
+            other_split,
+            Some(gold_name("安倍", "晋三")),
+        );
+        assert_eq!(comparison.num_names, 2);
+        assert_eq!(comparison.num_agreed, 1);
+        assert_eq!(comparison.agreement, 0.5);
+        let gold_comparison = comparison.gold.as_ref().unwrap();
+        assert_eq!(gold_comparison.num_correct_a, 2);
+        assert_eq!(gold_comparison.num_correct_b, 1);
+        assert_eq!(gold_comparison.only_a_correct, 1);
+        assert_eq!(gold_comparison.both_wrong, 0);
+        assert_eq!(comparison.differences.len(), 1);
+        assert_eq!(comparison.differences[0].gold.as_deref(), Some("安倍/晋三"));
+
+        let lines = format_comparison(&comparison);
+        assert!(lines[0].starts_with("安倍晋三\tA: 安倍 晋三 ("));
+        assert!(lines[0].ends_with("\tgold: 安倍/晋三"));
+        assert_eq!(
+            lines[1],
+            "agreement: 0.5000 (1/2), A: basic, B: basic,no-normalize"
+        );
+        assert_eq!(lines[2], "accuracy A: 1.0000 (2/2), B: 0.5000 (1/2)");
+        assert_eq!(
+            lines[3],
+            "only A correct: 1, only B correct: 0, both wrong: 0"
+        );
+    }
+
+    #[test]
+    fn csv_name_column_is_found_by_header_name_only() {
+        let header = ["id".to_string(), "1".to_string(), "name".to_string()];
+        let name_column = CsvNameColumn::find(Some(&header), "1", "in.csv").unwrap();
+        assert_eq!(name_column.column_idx, 1);
+        let name_column = CsvNameColumn::find(Some(&header), "name", "in.csv").unwrap();
+        assert_eq!(name_column.column_idx, 2);
+        assert_eq!(name_column.num_input_columns, 3);
+        assert!(CsvNameColumn::find(Some(&header), "2", "in.csv").is_err());
+
+        let name_column = CsvNameColumn::find(None, "2", "in.csv").unwrap();
+        assert_eq!(name_column.column_idx, 2);
+        let error = CsvNameColumn::find(None, "name", "in.csv").err().unwrap();
+        assert_eq!(error.exit_code(), EXIT_USAGE);
+    }
+
+    #[test]
+    fn csv_records_get_the_divided_name_appended() {
+        let divider = create_divider(Mode::Basic, &repl_options(&[])).unwrap();
+        let header = ["id".to_string(), "name".to_string(), "note".to_string()];
+        let name_column = CsvNameColumn::find(Some(&header), "name", "in.csv").unwrap();
+        let record = |fields: &[&str]| fields.iter().map(|field| field.to_string()).collect();
+
+        let (fields, failure) = name_column.divide(&*divider, record(&["1", " 菅義偉 ", "x"]));
+        assert_eq!(fields[..5], ["1", " 菅義偉 ", "x", "菅", "義偉"]);
+        assert_eq!(fields.len(), 7);
+        assert!(failure.is_none());
+
+        // Short records are padded so the appended columns stay under their headers.
+        let (fields, failure) = name_column.divide(&*divider, record(&["2"]));
+        assert_eq!(fields, ["2", "", "", "", "", "", ""]);
+        assert!(failure.is_none());
+
+        let (fields, failure) = name_column.divide(&*divider, record(&["3", "菅", ""]));
+        assert_eq!(fields.len(), 7);
+        assert!(fields[3..].iter().all(|field| field.is_empty()));
+        assert!(failure.is_some());
+    }
+
+    #[test]
+    fn csv_formatter_quotes_fields_of_every_record() {
+        let mut formatter = CsvRecordFormatter::new(b',');
+        let record = |fields: &[&str]| -> Vec<String> {
+            fields.iter().map(|field| field.to_string()).collect()
+        };
+        assert_eq!(formatter.format(&record(&["a", "b"])), "a,b");
+        assert_eq!(
+            formatter.format(&record(&["菅, 義偉", "say \"hi\"", ""])),
+            "\"菅, 義偉\",\"say \"\"hi\"\"\","
+        );
+        assert_eq!(formatter.format(&record(&["c"])), "c");
+
+        let mut formatter = CsvRecordFormatter::new(b'\t');
+        assert_eq!(formatter.format(&record(&["a,b", "c"])), "a,b\tc");
+    }
+
+    fn gold_file_options(args: &[&str]) -> GoldFileOptions {
+        let mut argv = vec!["namedivider-rs", "build-stats", "gold.txt", "kanji.json"];
+        argv.extend(args);
+        match AppArg::try_parse_from(argv).unwrap().action {
+            Action::BuildStats {
+                gold_file_options, ..
+            } => gold_file_options,
+            _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn gold_file_options_read_a_separator_or_columns() {
+        let options = gold_file_options(&[]);
+        let gold_name = options.parse_line("菅 義偉").unwrap();
+        assert_eq!(
+            (gold_name.family, gold_name.given),
+            ("菅".into(), "義偉".into())
+        );
+        assert!(options.parse_line("菅義偉").is_none());
+
+        let options = gold_file_options(&["--gold-separator", "/"]);
+        let gold_name = options.parse_line("菅/義偉").unwrap();
+        assert_eq!(
+            (gold_name.family, gold_name.given),
+            ("菅".into(), "義偉".into())
+        );
+
+        let options = gold_file_options(&["--gold-columns", "2,1"]);
+        assert!(options.check().is_ok());
+        let gold_name = options.parse_line("1\t義偉\t菅").unwrap();
+        assert_eq!(
+            (gold_name.family, gold_name.given),
+            ("菅".into(), "義偉".into())
+        );
+
+        let options = gold_file_options(&["--gold-columns", "0,1,2"]);
+        assert_eq!(options.check().err().unwrap().exit_code(), EXIT_USAGE);
+    }
 }
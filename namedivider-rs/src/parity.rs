@@ -0,0 +1,164 @@
+use crate::divider::divided_name::DividedName;
+use serde::{Deserialize, Serialize};
+
+/// A division made by namedivider-python, read from one line of a JSONL fixture such as
+/// `{"name": "菅義偉", "family": "菅", "given": "義偉", "score": 0.67, "algorithm": "gbdt"}`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ReferenceDivision {
+    /// The undivided name; the family and the given name joined when absent.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub family: String,
+    pub given: String,
+    pub score: f64,
+    #[serde(default)]
+    pub algorithm: Option<String>,
+}
+
+impl ReferenceDivision {
+    pub fn undivided_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.family.clone() + &self.given,
+        }
+    }
+}
+
+pub fn parse_reference_division(line: &str) -> Result<ReferenceDivision, &'static str> {
+    let reference: ReferenceDivision =
+        serde_json::from_str(line).map_err(|_| "Invalid reference division.")?;
+    if reference.family.is_empty() || reference.given.is_empty() {
+        return Err("Reference division needs a family and a given name.");
+    }
+    Ok(reference)
+}
+
+/// A name whose division differs from the reference in its split, its algorithm, or its
+/// score by more than the tolerance.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ParityDifference {
+    pub name: String,
+    pub reference_family: String,
+    pub reference_given: String,
+    pub reference_score: f64,
+    pub reference_algorithm: Option<String>,
+    pub family: String,
+    pub given: String,
+    pub score: f64,
+    pub algorithm: String,
+    pub is_same_split: bool,
+    /// This score minus the reference score.
+    pub score_delta: f64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ParityReport {
+    pub num_names: usize,
+    pub num_same_split: usize,
+    pub split_agreement: f64,
+    /// Names whose reference has an algorithm.
+    pub num_with_algorithm: usize,
+    pub num_same_algorithm: usize,
+    pub tolerance: f64,
+    /// Names with the same split and a score within the tolerance of the reference.
+    pub num_within_tolerance: usize,
+    /// Score deltas of the names with the same split; deltas of different splits compare
+    /// scores of different things.
+    pub mean_score_delta: f64,
+    pub mean_abs_score_delta: f64,
+    pub max_abs_score_delta: f64,
+    pub differences: Vec<ParityDifference>,
+}
+
+/// Compares divisions with their references and summarizes them into a `ParityReport`.
+pub struct ParityReportBuilder {
+    tolerance: f64,
+    num_names: usize,
+    num_same_split: usize,
+    num_with_algorithm: usize,
+    num_same_algorithm: usize,
+    num_within_tolerance: usize,
+    score_delta_sum: f64,
+    abs_score_delta_sum: f64,
+    max_abs_score_delta: f64,
+    differences: Vec<ParityDifference>,
+}
+
+impl ParityReportBuilder {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            num_names: 0,
+            num_same_split: 0,
+            num_with_algorithm: 0,
+            num_same_algorithm: 0,
+            num_within_tolerance: 0,
+            score_delta_sum: 0.0,
+            abs_score_delta_sum: 0.0,
+            max_abs_score_delta: 0.0,
+            differences: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, reference: &ReferenceDivision, divided_name: &DividedName) {
+        self.num_names += 1;
+        let algorithm = divided_name.algorithm();
+        let is_same_split =
+            divided_name.family == reference.family && divided_name.given == reference.given;
+        let is_same_algorithm = match &reference.algorithm {
+            Some(reference_algorithm) => *reference_algorithm == algorithm,
+            None => true,
+        };
+        let score_delta = divided_name.score - reference.score;
+        let is_within_tolerance = score_delta.abs() <= self.tolerance;
+        if reference.algorithm.is_some() {
+            self.num_with_algorithm += 1;
+            self.num_same_algorithm += is_same_algorithm as usize;
+        }
+        if is_same_split {
+            self.num_same_split += 1;
+            self.num_within_tolerance += is_within_tolerance as usize;
+            self.score_delta_sum += score_delta;
+            self.abs_score_delta_sum += score_delta.abs();
+            self.max_abs_score_delta = self.max_abs_score_delta.max(score_delta.abs());
+        }
+        if !is_same_split || !is_same_algorithm || !is_within_tolerance {
+            self.differences.push(ParityDifference {
+                name: reference.undivided_name(),
+                reference_family: reference.family.clone(),
+                reference_given: reference.given.clone(),
+                reference_score: reference.score,
+                reference_algorithm: reference.algorithm.clone(),
+                family: divided_name.family.clone(),
+                given: divided_name.given.clone(),
+                score: divided_name.score,
+                algorithm,
+                is_same_split,
+                score_delta,
+            });
+        }
+    }
+
+    pub fn build(self) -> ParityReport {
+        let ratio = |numerator: f64, denominator: usize| {
+            if denominator == 0 {
+                0.0
+            } else {
+                numerator / denominator as f64
+            }
+        };
+        ParityReport {
+            num_names: self.num_names,
+            num_same_split: self.num_same_split,
+            split_agreement: ratio(self.num_same_split as f64, self.num_names),
+            num_with_algorithm: self.num_with_algorithm,
+            num_same_algorithm: self.num_same_algorithm,
+            tolerance: self.tolerance,
+            num_within_tolerance: self.num_within_tolerance,
+            mean_score_delta: ratio(self.score_delta_sum, self.num_same_split),
+            mean_abs_score_delta: ratio(self.abs_score_delta_sum, self.num_same_split),
+            max_abs_score_delta: self.max_abs_score_delta,
+            differences: self.differences,
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::text_encoding::{decode_text, InvalidTextPolicy, TextEncoding};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+/// Reads lines a batch at a time, decoding each line on its own so that input of
+/// any size is never held in memory at once.
+///
+/// A batch ends after `batch_size` lines, or earlier when no more input is buffered,
+/// so that a line written to a pipe is processed without waiting for the ones after it.
+pub struct LineBatches<R> {
+    reader: BufReader<R>,
+    encoding: TextEncoding,
+    policy: InvalidTextPolicy,
+    batch_size: usize,
+    num_lines: usize,
+    num_bytes: usize,
+    finished: bool,
+}
+
+impl<R: Read> LineBatches<R> {
+    pub fn new(
+        reader: R,
+        encoding: TextEncoding,
+        policy: InvalidTextPolicy,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            encoding,
+            policy,
+            batch_size: batch_size.max(1),
+            num_lines: 0,
+            num_bytes: 0,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for LineBatches<R> {
+    type Item = Result<Vec<String>, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut batch = Vec::new();
+        let mut bytes = Vec::new();
+        while batch.len() < self.batch_size {
+            bytes.clear();
+            let read = match self.reader.read_until(b'\n', &mut bytes) {
+                Ok(read) => read,
+                Err(why) => {
+                    self.finished = true;
+                    return Some(Err(why));
+                }
+            };
+            if read == 0 {
+                self.finished = true;
+                break;
+            }
+            let line = bytes
+                .strip_suffix(b"\n")
+                .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+                .unwrap_or(&bytes);
+            match decode_text(line, self.encoding, self.policy) {
+                Ok(line) => batch.push(line),
+                Err(why) => {
+                    self.finished = true;
+                    return Some(Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        why.with_offset(self.num_lines, self.num_bytes),
+                    )));
+                }
+            }
+            self.num_lines += 1;
+            self.num_bytes += read;
+            if self.reader.buffer().is_empty() {
+                break;
+            }
+        }
+        if batch.is_empty() {
+            None
+        } else {
+            Some(Ok(batch))
+        }
+    }
+}
+
+/// Runs `process` over the items of `batches` on `num_workers` threads and passes
+/// the results to `write` in input order, one batch at a time.
+///
+/// Every thread shares `worker`, such as a divider, so it is built once by the caller.
+/// Reading stops while `2 * num_workers` batches are read but not yet written, which
+/// bounds the results held back until the batches before them are done.
+pub fn process_in_order<T, W, O>(
+    batches: impl Iterator<Item = Vec<T>> + Send,
+    num_workers: usize,
+    worker: &W,
+    process: impl Fn(&W, T) -> O + Sync,
+    mut write: impl FnMut(Vec<O>),
+) where
+    T: Send,
+    W: Sync + ?Sized,
+    O: Send,
+{
+    let num_workers = num_workers.max(1);
+    let max_batches_in_flight = num_workers * 2;
+    let (batch_sender, batch_receiver) = mpsc::sync_channel::<(usize, Vec<T>)>(num_workers * 2);
+    let (result_sender, result_receiver) = mpsc::sync_channel::<(usize, Vec<O>)>(num_workers * 2);
+    // One permit per batch that may be in flight, given back once the batch is written.
+    let (permit_sender, permit_receiver) = mpsc::channel::<()>();
+    for _ in 0..max_batches_in_flight {
+        permit_sender.send(()).unwrap();
+    }
+    let batch_receiver = Mutex::new(batch_receiver);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut batches = batches.enumerate();
+            while permit_receiver.recv().is_ok() {
+                let (batch_idx, batch) = match batches.next() {
+                    Some(batch) => batch,
+                    None => break,
+                };
+                if batch_sender.send((batch_idx, batch)).is_err() {
+                    break;
+                }
+            }
+        });
+        for _ in 0..num_workers {
+            let result_sender = result_sender.clone();
+            let batch_receiver = &batch_receiver;
+            let process = &process;
+            scope.spawn(move || loop {
+                let received = batch_receiver.lock().unwrap().recv();
+                let (batch_idx, batch) = match received {
+                    Ok(received) => received,
+                    Err(_) => break,
+                };
+                let results = batch
+                    .into_iter()
+                    .map(|item| process(worker, item))
+                    .collect();
+                if result_sender.send((batch_idx, results)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut pending_results = BTreeMap::new();
+        let mut next_batch_idx = 0;
+        for (batch_idx, results) in result_receiver {
+            pending_results.insert(batch_idx, results);
+            while let Some(results) = pending_results.remove(&next_batch_idx) {
+                write(results);
+                next_batch_idx += 1;
+                // The reader is gone once every batch is read, so a failed send is fine.
+                let _ = permit_sender.send(());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::{process_in_order, LineBatches};
+    use crate::text_encoding::{InvalidTextPolicy, TextEncoding};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_line_batches() {
+        let input: &[u8] = b"\x8d\xb2\x93\xa1\r\n\x97\xe9\x96\xd8\n\n\x93\x63\x92\x86";
+        let batches: Vec<Vec<String>> =
+            LineBatches::new(input, TextEncoding::ShiftJis, InvalidTextPolicy::Strict, 2)
+                .map(|batch| batch.unwrap())
+                .collect();
+        assert_eq!(
+            batches,
+            vec![
+                vec!["佐藤".to_string(), "鈴木".to_string()],
+                vec!["".to_string(), "田中".to_string()]
+            ]
+        );
+
+        let broken: &[u8] = b"\x8d\xb2\x93\xa1\n\x97\xe9\xff\n";
+        let error = LineBatches::new(broken, TextEncoding::ShiftJis, InvalidTextPolicy::Strict, 1)
+            .find_map(|batch| batch.err())
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid Shift_JIS byte sequence at line 2 (byte 7)"
+        );
+    }
+
+    #[test]
+    fn test_process_in_order() {
+        let num_batches_read = AtomicUsize::new(0);
+        let batches = (0..100).map(|i| {
+            num_batches_read.fetch_add(1, Ordering::SeqCst);
+            (i * 10..i * 10 + 10).collect::<Vec<usize>>()
+        });
+        let mut results = Vec::new();
+        let mut max_batches_ahead = 0;
+        process_in_order(
+            batches,
+            4,
+            &1,
+            |offset, item| {
+                if item % 7 == 0 {
+                    std::thread::sleep(std::time::Duration::from_micros(100));
+                }
+                item + offset
+            },
+            |batch| {
+                results.extend(batch);
+                let num_batches_written = results.len() / 10;
+                let num_batches_ahead =
+                    num_batches_read.load(Ordering::SeqCst) - num_batches_written;
+                max_batches_ahead = max_batches_ahead.max(num_batches_ahead);
+            },
+        );
+        assert_eq!(results, (1..1001).collect::<Vec<usize>>());
+        // 8 batches in flight at most, the one just written among them.
+        assert!(max_batches_ahead < 8);
+    }
+}
@@ -0,0 +1,287 @@
+use clap::ValueEnum;
+use encoding_rs::{DecoderResult, EncoderResult, Encoding, EUC_JP, SHIFT_JIS};
+use std::fmt;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Text encodings of name lists read and written by the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TextEncoding {
+    #[value(name = "utf-8", alias = "utf8")]
+    Utf8,
+    /// UTF-8 starting with a byte order mark, as written by Excel.
+    #[value(
+        name = "utf-8-bom",
+        alias = "utf-8-sig",
+        alias = "utf8bom",
+        alias = "utf8sig"
+    )]
+    Utf8Bom,
+    /// Shift_JIS as extended by Windows (CP932).
+    #[value(
+        name = "shift_jis",
+        alias = "shift-jis",
+        alias = "sjis",
+        alias = "cp932",
+        alias = "windows-31j"
+    )]
+    ShiftJis,
+    #[value(name = "euc-jp", alias = "euc_jp", alias = "eucjp")]
+    EucJp,
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEncoding::Utf8 => write!(f, "UTF-8"),
+            TextEncoding::Utf8Bom => write!(f, "UTF-8 with BOM"),
+            TextEncoding::ShiftJis => write!(f, "Shift_JIS"),
+            TextEncoding::EucJp => write!(f, "EUC-JP"),
+        }
+    }
+}
+
+impl TextEncoding {
+    fn legacy_encoding(&self) -> Option<&'static Encoding> {
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => None,
+            TextEncoding::ShiftJis => Some(SHIFT_JIS),
+            TextEncoding::EucJp => Some(EUC_JP),
+        }
+    }
+}
+
+/// What to do with bytes that are invalid in the input encoding, or chars that the
+/// output encoding cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InvalidTextPolicy {
+    /// Stop with an error naming the line.
+    Strict,
+    /// Decode invalid bytes as U+FFFD and encode unrepresentable chars as `?`.
+    Replace,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextEncodingError {
+    /// Bytes that are not valid in the encoding, at a byte offset of the input.
+    InvalidBytes {
+        encoding: TextEncoding,
+        line: usize,
+        byte_offset: usize,
+    },
+    /// A char that the encoding cannot represent.
+    Unencodable {
+        encoding: TextEncoding,
+        line: usize,
+        c: char,
+    },
+}
+
+impl fmt::Display for TextEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEncodingError::InvalidBytes {
+                encoding,
+                line,
+                byte_offset,
+            } => write!(
+                f,
+                "invalid {} byte sequence at line {} (byte {})",
+                encoding, line, byte_offset
+            ),
+            TextEncodingError::Unencodable { encoding, line, c } => write!(
+                f,
+                "{} (U+{:04X}) at line {} cannot be encoded in {}",
+                c, *c as u32, line, encoding
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextEncodingError {}
+
+impl TextEncodingError {
+    /// Moves the position past the lines and bytes that came before the decoded or
+    /// encoded text, for text processed a piece at a time.
+    pub fn with_offset(self, num_lines_before: usize, num_bytes_before: usize) -> Self {
+        match self {
+            TextEncodingError::InvalidBytes {
+                encoding,
+                line,
+                byte_offset,
+            } => TextEncodingError::InvalidBytes {
+                encoding,
+                line: line + num_lines_before,
+                byte_offset: byte_offset + num_bytes_before,
+            },
+            TextEncodingError::Unencodable { encoding, line, c } => {
+                TextEncodingError::Unencodable {
+                    encoding,
+                    line: line + num_lines_before,
+                    c,
+                }
+            }
+        }
+    }
+}
+
+/// Decodes `bytes` into a string. A leading UTF-8 BOM is dropped whichever UTF-8
+/// variant is given.
+pub fn decode_text(
+    bytes: &[u8],
+    encoding: TextEncoding,
+    policy: InvalidTextPolicy,
+) -> Result<String, TextEncodingError> {
+    let invalid_bytes_at = |byte_offset: usize, bytes: &[u8]| TextEncodingError::InvalidBytes {
+        encoding,
+        line: bytes[..byte_offset].iter().filter(|b| **b == b'\n').count() + 1,
+        byte_offset,
+    };
+    let legacy_encoding = match encoding.legacy_encoding() {
+        Some(legacy_encoding) => legacy_encoding,
+        None => {
+            let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+            return match policy {
+                InvalidTextPolicy::Strict => std::str::from_utf8(bytes)
+                    .map(|s| s.to_string())
+                    .map_err(|why| invalid_bytes_at(why.valid_up_to(), bytes)),
+                InvalidTextPolicy::Replace => Ok(String::from_utf8_lossy(bytes).to_string()),
+            };
+        }
+    };
+    if policy == InvalidTextPolicy::Replace {
+        let (decoded, _) = legacy_encoding.decode_without_bom_handling(bytes);
+        return Ok(decoded.to_string());
+    }
+    let mut decoder = legacy_encoding.new_decoder_without_bom_handling();
+    let mut decoded = String::with_capacity(bytes.len() * 2);
+    let mut read_total = 0;
+    loop {
+        let (result, read) =
+            decoder.decode_to_string_without_replacement(&bytes[read_total..], &mut decoded, true);
+        read_total += read;
+        match result {
+            DecoderResult::InputEmpty => return Ok(decoded),
+            DecoderResult::OutputFull => decoded.reserve(bytes.len() - read_total + 16),
+            DecoderResult::Malformed(malformed_length, extra_length) => {
+                let byte_offset = read_total - malformed_length as usize - extra_length as usize;
+                return Err(invalid_bytes_at(byte_offset, bytes));
+            }
+        }
+    }
+}
+
+/// Encodes `text`. The BOM of `TextEncoding::Utf8Bom` is not included, since it
+/// belongs only at the start of a file; see `byte_order_mark`.
+pub fn encode_text(
+    text: &str,
+    encoding: TextEncoding,
+    policy: InvalidTextPolicy,
+) -> Result<Vec<u8>, TextEncodingError> {
+    let legacy_encoding = match encoding.legacy_encoding() {
+        Some(legacy_encoding) => legacy_encoding,
+        None => return Ok(text.as_bytes().to_vec()),
+    };
+    let mut encoder = legacy_encoding.new_encoder();
+    let mut encoded = Vec::with_capacity(text.len());
+    let mut read_total = 0;
+    loop {
+        let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(
+            &text[read_total..],
+            &mut encoded,
+            true,
+        );
+        read_total += read;
+        match result {
+            EncoderResult::InputEmpty => return Ok(encoded),
+            EncoderResult::OutputFull => encoded.reserve(text.len() - read_total + 16),
+            EncoderResult::Unmappable(c) => match policy {
+                InvalidTextPolicy::Strict => {
+                    let line = text[..read_total - c.len_utf8()].matches('\n').count() + 1;
+                    return Err(TextEncodingError::Unencodable { encoding, line, c });
+                }
+                InvalidTextPolicy::Replace => encoded.push(b'?'),
+            },
+        }
+    }
+}
+
+/// The bytes to write before the first line of a file in `encoding`.
+pub fn byte_order_mark(encoding: TextEncoding) -> &'static [u8] {
+    match encoding {
+        TextEncoding::Utf8Bom => UTF8_BOM,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text_encoding::{
+        decode_text, encode_text, InvalidTextPolicy, TextEncoding, TextEncodingError,
+    };
+
+    #[test]
+    fn test_decode_text() {
+        let shift_jis = b"\x8d\xb2\x93\xa1 \x88\xea\x98\x59\n\x97\xe9\x96\xd8";
+        assert_eq!(
+            decode_text(shift_jis, TextEncoding::ShiftJis, InvalidTextPolicy::Strict).unwrap(),
+            "佐藤 一郎\n鈴木"
+        );
+        let euc_jp = b"\xba\xb4\xc6\xa3";
+        assert_eq!(
+            decode_text(euc_jp, TextEncoding::EucJp, InvalidTextPolicy::Strict).unwrap(),
+            "佐藤"
+        );
+        let utf8_bom = "\u{FEFF}佐藤".as_bytes();
+        for encoding in [TextEncoding::Utf8, TextEncoding::Utf8Bom] {
+            assert_eq!(
+                decode_text(utf8_bom, encoding, InvalidTextPolicy::Strict).unwrap(),
+                "佐藤"
+            );
+        }
+
+        let broken = b"\x8d\xb2\x93\xa1\n\x97\xe9\xff";
+        assert_eq!(
+            decode_text(broken, TextEncoding::ShiftJis, InvalidTextPolicy::Strict),
+            Err(TextEncodingError::InvalidBytes {
+                encoding: TextEncoding::ShiftJis,
+                line: 2,
+                byte_offset: 7,
+            })
+        );
+        assert_eq!(
+            decode_text(broken, TextEncoding::ShiftJis, InvalidTextPolicy::Replace).unwrap(),
+            "佐藤\n鈴\u{FFFD}"
+        );
+        assert!(decode_text(shift_jis, TextEncoding::Utf8, InvalidTextPolicy::Strict).is_err());
+    }
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(
+            encode_text(
+                "佐藤 一郎",
+                TextEncoding::ShiftJis,
+                InvalidTextPolicy::Strict
+            )
+            .unwrap(),
+            b"\x8d\xb2\x93\xa1 \x88\xea\x98\x59"
+        );
+        assert_eq!(
+            encode_text(
+                "佐藤\n𠮷田",
+                TextEncoding::ShiftJis,
+                InvalidTextPolicy::Strict
+            ),
+            Err(TextEncodingError::Unencodable {
+                encoding: TextEncoding::ShiftJis,
+                line: 2,
+                c: '𠮷',
+            })
+        );
+        assert_eq!(
+            encode_text("𠮷田", TextEncoding::EucJp, InvalidTextPolicy::Replace).unwrap(),
+            b"?\xc5\xc4"
+        );
+    }
+}
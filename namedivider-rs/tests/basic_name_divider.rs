@@ -1,7 +1,19 @@
+#[allow(deprecated)]
 use namedivider_rs::divider::basic_name_divider::get_basic_name_divider;
+use namedivider_rs::divider::basic_name_divider::{
+    get_basic_name_divider_with_weights, BasicNameDivider,
+};
+use namedivider_rs::divider::basic_score_weights::BasicScoreWeights;
+use namedivider_rs::divider::divided_name::Span;
 use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::provenance::{Provenance, RuleId};
+use namedivider_rs::divider::score_calculator::ScoreCalculator;
+use namedivider_rs::feature::gaiji::GaijiMapping;
+use namedivider_rs::feature::user_dictionary::UserDictionary;
+use std::sync::Arc;
 
 #[test]
+#[allow(deprecated)]
 fn divide_rule_two_char() {
     let undivided_name = "原敬".to_string();
     let divider = get_basic_name_divider(" ".to_string(), true, "kanji_feature".to_string(), false);
@@ -11,9 +23,16 @@ fn divide_rule_two_char() {
     assert_eq!(divided_name.separator, " ".to_string());
     assert_eq!(divided_name.score, 1.0);
     assert_eq!(divided_name.algorithm, "rule".to_string());
+    assert_eq!(
+        divided_name.provenance,
+        Provenance::Rule {
+            rule_id: RuleId::TwoChars
+        }
+    );
 }
 
 #[test]
+#[allow(deprecated)]
 fn divide_rule_with_kana() {
     let undivided_name = "中山マサ".to_string();
     let divider = get_basic_name_divider(" ".to_string(), true, "kanji_feature".to_string(), false);
@@ -23,9 +42,16 @@ fn divide_rule_with_kana() {
     assert_eq!(divided_name.separator, " ".to_string());
     assert_eq!(divided_name.score, 1.0);
     assert_eq!(divided_name.algorithm, "rule".to_string());
+    assert_eq!(
+        divided_name.provenance,
+        Provenance::Rule {
+            rule_id: RuleId::ScriptBoundary
+        }
+    );
 }
 
 #[test]
+#[allow(deprecated)]
 fn divide_with_feature() {
     let undivided_name = "菅義偉".to_string();
     let divider = get_basic_name_divider(" ".to_string(), true, "kanji_feature".to_string(), false);
@@ -35,4 +61,198 @@ fn divide_with_feature() {
     assert_eq!(divided_name.separator, " ".to_string());
     assert_eq!(divided_name.score, 0.6328842762252201);
     assert_eq!(divided_name.algorithm, "kanji_feature".to_string());
+    assert_eq!(divided_name.provenance, Provenance::Basic);
+}
+
+#[test]
+fn divide_with_order_only_weights() {
+    let undivided_name = "菅義偉".to_string();
+    let weights = BasicScoreWeights {
+        order_weight_3: 1.0,
+        ..BasicScoreWeights::default()
+    };
+    let divider = get_basic_name_divider_with_weights(" ".to_string(), true, false, weights);
+    let divided_name = divider.divide_name(&undivided_name);
+    let (order_score, _) = divider
+        .basic_score_calculator
+        .calc_order_and_length_scores("菅", "義偉");
+    assert_eq!(
+        divider.basic_score_calculator.calc_score("菅", "義偉"),
+        order_score
+    );
+    assert_eq!(divided_name.family, "菅".to_string());
+    assert_eq!(divided_name.given, "義偉".to_string());
+}
+
+#[test]
+fn divide_with_normalization_keeps_original_spans() {
+    let undivided_name = "𠮷田太郎".to_string();
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let divided_name = divider.divide_name(&undivided_name);
+    assert_eq!(divided_name.family, "𠮷田".to_string());
+    assert_eq!(divided_name.given, "太郎".to_string());
+    assert_eq!(divided_name.normalized_family, "吉田".to_string());
+    assert_eq!(divided_name.normalized_given, "太郎".to_string());
+    assert_eq!(
+        divided_name.family_span,
+        Span {
+            char_start: 0,
+            char_end: 2,
+            byte_start: 0,
+            byte_end: 7,
+        }
+    );
+    assert_eq!(
+        divided_name.given_span,
+        Span {
+            char_start: 2,
+            char_end: 4,
+            byte_start: 7,
+            byte_end: 13,
+        }
+    );
+}
+
+#[test]
+fn divide_with_variation_selectors() {
+    let divider = BasicNameDivider::new(" ".to_string(), false, false);
+    let plain = divider.divide_name(&"葛西太郎".to_string());
+    // 葛 with an IVS selector and 郎 followed by a combining mark.
+    let divided_name = divider.divide_name(&"葛\u{E0100}西太郎\u{0301}".to_string());
+    assert_eq!(divided_name.family, "葛\u{E0100}西".to_string());
+    assert_eq!(divided_name.given, "太郎\u{0301}".to_string());
+    assert_eq!(divided_name.normalized_family, plain.family);
+    assert_eq!(divided_name.normalized_given, plain.given);
+    assert_eq!(divided_name.score, plain.score);
+    assert_eq!(
+        divided_name.given_span,
+        Span {
+            char_start: 3,
+            char_end: 6,
+            byte_start: 10,
+            byte_end: 18,
+        }
+    );
+}
+
+#[test]
+fn variation_selector_does_not_count_as_a_char() {
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let divided_name = divider.divide_name(&"菅\u{FE00}義".to_string());
+    assert_eq!(divided_name.family, "菅\u{FE00}".to_string());
+    assert_eq!(divided_name.given, "義".to_string());
+    assert_eq!(
+        divided_name.provenance,
+        Provenance::Rule {
+            rule_id: RuleId::TwoChars
+        }
+    );
+}
+
+#[test]
+fn try_divide_name_refuses_a_single_char() {
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    assert!(divider.try_divide_name("菅").is_err());
+    assert!(divider.try_divide_name("菅\u{FE00}").is_err());
+    assert!(divider.try_divide_name_candidates("菅").is_err());
+    // A leading combining mark has no base char to join, so it is a unit of its own.
+    let divided_name = divider.try_divide_name("\u{3099}菅").unwrap();
+    assert_eq!(divided_name.family, "\u{3099}".to_string());
+    assert_eq!(divided_name.given, "菅".to_string());
+}
+
+#[test]
+fn divide_with_gaiji_mapping() {
+    let gaiji_mapping = GaijiMapping::from_text("U+E000\t葛\n").unwrap();
+    let mut divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let plain = divider.divide_name(&"葛西太郎".to_string());
+    divider.name_divider_base.gaiji_mapping = Some(Arc::new(gaiji_mapping));
+    let divided_name = divider.divide_name(&"\u{E000}西太郎".to_string());
+    assert_eq!(divided_name.family, "\u{E000}西".to_string());
+    assert_eq!(divided_name.given, "太郎".to_string());
+    assert_eq!(divided_name.normalized_family, "葛西".to_string());
+    assert_eq!(divided_name.score, plain.score);
+}
+
+#[test]
+fn divide_name_candidates() {
+    let undivided_name = "菅義偉".to_string();
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let candidates = divider.divide_name_candidates(&undivided_name);
+    assert_eq!(candidates.len(), 2);
+    let divided_name = divider.divide_name(&undivided_name);
+    assert_eq!(candidates[0].family, divided_name.family);
+    assert_eq!(candidates[0].score, divided_name.score);
+    assert_eq!(candidates[1].family, "菅義".to_string());
+    assert!((candidates[0].score + candidates[1].score - 1.0).abs() < 1e-9);
+
+    let candidates = divider.divide_name_candidates(&"中山マサ".to_string());
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].algorithm(), "rule".to_string());
+}
+
+#[test]
+fn divide_with_user_dictionary() {
+    let user_dictionary = UserDictionary::from_text("菅義 偉\n").unwrap();
+    let mut divider = BasicNameDivider::new(" ".to_string(), true, false);
+    divider.name_divider_base.user_dictionary = Some(Arc::new(user_dictionary));
+    let divided_name = divider.divide_name(&"菅義偉".to_string());
+    assert_eq!(divided_name.family, "菅義".to_string());
+    assert_eq!(divided_name.given, "偉".to_string());
+    assert_eq!(divided_name.score, 1.0);
+    assert_eq!(divided_name.provenance, Provenance::UserDictionary);
+    let candidates = divider.divide_name_candidates(&"菅義偉".to_string());
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].family, "菅義".to_string());
+
+    let divided_name = divider.divide_name(&"安倍晋三".to_string());
+    assert_eq!(divided_name.provenance, Provenance::Basic);
+}
+
+#[test]
+#[allow(deprecated)]
+fn divide_with_algorithm_name() {
+    let divider = get_basic_name_divider(" ".to_string(), true, "my_basic".to_string(), false);
+    let divided_name = divider.divide_name(&"菅義偉".to_string());
+    assert_eq!(divided_name.algorithm, "my_basic".to_string());
+    assert_eq!(divided_name.algorithm(), "my_basic".to_string());
+    let divided_name = divider.divide_name(&"原敬".to_string());
+    assert_eq!(divided_name.algorithm, "rule".to_string());
+}
+
+#[test]
+fn divide_pre_separated_name() {
+    let divider = BasicNameDivider::new("/".to_string(), true, false);
+    for (undivided_name, given_span) in [
+        ("菅 義偉", (2, 4, 4, 10)),
+        ("菅\u{3000}義偉", (2, 4, 6, 12)),
+        ("菅/義偉", (2, 4, 4, 10)),
+    ] {
+        let divided_name = divider.divide_name(&undivided_name.to_string());
+        assert_eq!(divided_name.family, "菅".to_string());
+        assert_eq!(divided_name.given, "義偉".to_string());
+        assert_eq!(divided_name.score, 1.0);
+        assert_eq!(divided_name.provenance, Provenance::PreSeparated);
+        let (char_start, char_end, byte_start, byte_end) = given_span;
+        assert_eq!(
+            divided_name.given_span,
+            Span {
+                char_start,
+                char_end,
+                byte_start,
+                byte_end,
+            }
+        );
+        let candidates = divider.divide_name_candidates(&undivided_name.to_string());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].provenance, Provenance::PreSeparated);
+    }
+
+    let divided_name = divider.divide_name(&"髙橋 一郎".to_string());
+    assert_eq!(divided_name.family, "髙橋".to_string());
+    assert_eq!(divided_name.normalized_family, "高橋".to_string());
+
+    // More than one separator is left to the score calculator.
+    let divided_name = divider.divide_name(&"菅 義 偉".to_string());
+    assert_ne!(divided_name.provenance, Provenance::PreSeparated);
 }
@@ -0,0 +1,118 @@
+use namedivider_rs::divider::basic_name_divider::get_basic_name_divider_with_repository;
+use namedivider_rs::divider::basic_score_weights::BasicScoreWeights;
+use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::feature::assets::Asset;
+use namedivider_rs::feature::compiled_assets::{compile_assets, CompiledAssets};
+use namedivider_rs::feature::extractor::FamilyRankingFeatureExtractor;
+use namedivider_rs::feature::family_name::FamilyNameRepository;
+use namedivider_rs::feature::kanji::{parse_kanji_statistics, KanjiStatisticsRepository};
+use std::sync::Arc;
+
+fn embedded_asset(file_name: &str) -> String {
+    String::from_utf8(Asset::get(file_name).unwrap().data.to_vec()).unwrap()
+}
+
+fn compiled_embedded_assets() -> CompiledAssets {
+    let kanji_statistics = parse_kanji_statistics(&embedded_asset("kanji.json")).unwrap();
+    let bytes = compile_assets(
+        &kanji_statistics,
+        &embedded_asset("family_names.txt"),
+        None,
+        "",
+    )
+    .unwrap();
+    CompiledAssets::from_bytes(bytes).unwrap()
+}
+
+const NAMES: [&str; 6] = [
+    "菅義偉",
+    "中曽根康弘",
+    "武者小路実篤",
+    "髙橋一生",
+    "𠮷田沙保里",
+    "きゃりーぱみゅぱみゅ",
+];
+
+#[test]
+fn compiled_lookups_match_parsed_ones() {
+    let compiled_assets = compiled_embedded_assets();
+    let parsed_kanji = KanjiStatisticsRepository::from_json(&embedded_asset("kanji.json")).unwrap();
+    let compiled_kanji = KanjiStatisticsRepository::from_compiled(&compiled_assets);
+    for kanji in NAMES.concat().chars().chain(['々', '〆']) {
+        assert_eq!(compiled_kanji.contains(kanji), parsed_kanji.contains(kanji));
+        assert_eq!(
+            compiled_kanji.length_counts(kanji),
+            parsed_kanji.length_counts(kanji)
+        );
+        for mask_idx in 0..4 {
+            assert_eq!(
+                compiled_kanji.order_probabilities(kanji, mask_idx),
+                parsed_kanji.order_probabilities(kanji, mask_idx)
+            );
+        }
+        let key = kanji.to_string();
+        assert_eq!(
+            compiled_kanji.get(&key).order_counts,
+            parsed_kanji.get(&key).order_counts
+        );
+    }
+
+    let parsed_family_names = FamilyNameRepository::from_text(&embedded_asset("family_names.txt"));
+    let compiled_family_names = FamilyNameRepository::from_compiled(&compiled_assets);
+    assert_eq!(compiled_family_names.len(), parsed_family_names.len());
+    for family in ["菅", "中曽根", "武者小路", "佐藤", "鈴木", "田中", "菅義"] {
+        let (compiled_rank, parsed_rank) = (
+            compiled_family_names.get_rank(family),
+            parsed_family_names.get_rank(family),
+        );
+        assert!(compiled_rank == parsed_rank || compiled_rank.is_nan() && parsed_rank.is_nan());
+    }
+}
+
+#[test]
+fn dividers_on_compiled_assets_divide_as_on_embedded_ones() {
+    let compiled_assets = compiled_embedded_assets();
+    let repository = |kanji_statistics_repository| {
+        get_basic_name_divider_with_repository(
+            " ".to_string(),
+            true,
+            false,
+            BasicScoreWeights::default(),
+            kanji_statistics_repository,
+        )
+    };
+    let parsed_divider = repository(Arc::new(
+        KanjiStatisticsRepository::from_json(&embedded_asset("kanji.json")).unwrap(),
+    ));
+    let compiled_divider = repository(Arc::new(KanjiStatisticsRepository::from_compiled(
+        &compiled_assets,
+    )));
+    let compiled_feature_extractor = FamilyRankingFeatureExtractor {
+        kanji_statistics_repository: Arc::new(KanjiStatisticsRepository::from_compiled(
+            &compiled_assets,
+        )),
+        family_name_repository: Arc::new(FamilyNameRepository::from_compiled(&compiled_assets)),
+    };
+    let parsed_feature_extractor = FamilyRankingFeatureExtractor::shared();
+    for name in NAMES {
+        let name = name.to_string();
+        let (compiled, parsed) = (
+            compiled_divider.divide_name(&name),
+            parsed_divider.divide_name(&name),
+        );
+        assert_eq!(
+            (&compiled.family, compiled.score),
+            (&parsed.family, parsed.score)
+        );
+        for (byte_idx, _) in name.char_indices().skip(1) {
+            let (family, given) = name.split_at(byte_idx);
+            let compiled = compiled_feature_extractor
+                .get_features(family, given)
+                .to_vec();
+            let parsed = parsed_feature_extractor
+                .get_features(family, given)
+                .to_vec();
+            assert_eq!(format!("{:?}", compiled), format!("{:?}", parsed));
+        }
+    }
+}
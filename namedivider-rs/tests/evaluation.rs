@@ -0,0 +1,52 @@
+use namedivider_rs::corpus::{parse_divided_name, parse_divided_name_columns};
+use namedivider_rs::divider::basic_name_divider::BasicNameDivider;
+use namedivider_rs::evaluation::{evaluate_name, AccuracyReportBuilder};
+
+#[test]
+fn accuracy_report() {
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let mut builder = AccuracyReportBuilder::new(2, vec![0.0, 0.99], 10);
+    for divided_name in ["菅 義偉", "菅義 偉", "中山 マサ", "原 敬"] {
+        let gold_name = parse_divided_name(divided_name, " ").unwrap();
+        builder.add(&evaluate_name(&divider, gold_name, 2));
+    }
+    let report = builder.build();
+    assert_eq!(report.num_names, 4);
+    assert_eq!(report.num_correct, 3);
+    assert_eq!(report.accuracy, 0.75);
+
+    let labels: Vec<&str> = report.by_length.iter().map(|b| b.label.as_str()).collect();
+    assert_eq!(labels, vec!["2", "3", "4"]);
+    assert_eq!(report.by_length[1].num_names, 2);
+    assert_eq!(report.by_length[1].num_correct, 1);
+    let labels: Vec<&str> = report
+        .by_family_given_length
+        .iter()
+        .map(|b| b.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["1+1", "1+2", "2+1", "2+2"]);
+    let labels: Vec<&str> = report
+        .by_provenance
+        .iter()
+        .map(|b| b.label.as_str())
+        .collect();
+    assert_eq!(labels, vec!["basic", "rule"]);
+
+    assert_eq!(report.coverage[0].num_names, 4);
+    assert_eq!(report.coverage[1].num_names, 2);
+    assert_eq!(report.coverage[1].accuracy, Some(1.0));
+    assert_eq!(report.calibration.len(), 10);
+    assert_eq!(report.calibration[9].num_names, 2);
+    assert!(report.expected_calibration_error > 0.0);
+
+    assert_eq!(report.top_k[0].num_correct, 3);
+    assert_eq!(report.top_k[1].num_correct, 4);
+}
+
+#[test]
+fn parse_gold_name_from_columns() {
+    let gold_name = parse_divided_name_columns("1\t菅\t義偉\tx", 1, 2).unwrap();
+    assert_eq!(gold_name.family, "菅".to_string());
+    assert_eq!(gold_name.given, "義偉".to_string());
+    assert!(parse_divided_name_columns("1\t菅", 1, 2).is_none());
+}
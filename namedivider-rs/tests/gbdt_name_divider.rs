@@ -1,7 +1,14 @@
+#[allow(deprecated)]
 use namedivider_rs::divider::gbdt_name_divider::get_gbdt_name_divider;
+use namedivider_rs::divider::gbdt_name_divider::{
+    get_gbdt_name_divider_with_model, GBDTNameDivider,
+};
 use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::provenance::Provenance;
+use namedivider_rs::feature::assets::Asset;
 
 #[test]
+#[allow(deprecated)]
 fn divide_rule_two_char() {
     let undivided_name = "原敬".to_string();
     let divider = get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string());
@@ -14,6 +21,7 @@ fn divide_rule_two_char() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn divide_rule_with_kana() {
     let undivided_name = "中山マサ".to_string();
     let divider = get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string());
@@ -26,6 +34,7 @@ fn divide_rule_with_kana() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn divide_with_feature() {
     let undivided_name = "菅義偉".to_string();
     let divider = get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string());
@@ -36,3 +45,22 @@ fn divide_with_feature() {
     // assert_eq!(divided_name.score, 1.0);
     assert_eq!(divided_name.algorithm, "gbdt".to_string());
 }
+
+#[test]
+fn divide_with_model() {
+    let model = Asset::get("gbdt_model_v1.txt").unwrap();
+    let model_string = String::from_utf8_lossy(model.data.as_ref()).to_string();
+    let divider =
+        get_gbdt_name_divider_with_model(" ".to_string(), true, model_string, "custom").unwrap();
+    let divided_name = divider.divide_name(&"菅義偉".to_string());
+    let embedded_divided_name =
+        GBDTNameDivider::new(" ".to_string(), true).divide_name(&"菅義偉".to_string());
+    assert_eq!(divided_name.family, embedded_divided_name.family);
+    assert_eq!(divided_name.score, embedded_divided_name.score);
+    assert_eq!(
+        divided_name.provenance,
+        Provenance::Gbdt {
+            model_version: "custom".to_string()
+        }
+    );
+}
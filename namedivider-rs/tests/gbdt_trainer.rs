@@ -0,0 +1,79 @@
+use namedivider_rs::corpus::parse_divided_names;
+use namedivider_rs::divider::gbdt_trainer::{
+    family_ranking_feature_rows, split_validation_names, GBDTTrainerConfig,
+};
+use namedivider_rs::divider::name_divider_base::NameDividerBase;
+use namedivider_rs::divider::provenance::Provenance;
+use namedivider_rs::feature::extractor::{
+    FamilyRankingFeatureExtractor, FAMILY_RANKING_FEATURE_NAMES,
+};
+
+#[test]
+fn feature_rows() {
+    let gold_names = parse_divided_names("菅 義偉\n原 敬\n中曽根 康弘\n", " ");
+    let feature_extractor = FamilyRankingFeatureExtractor::shared();
+    let name_divider_base = NameDividerBase::new(
+        " ".to_string(),
+        true,
+        Provenance::Gbdt {
+            model_version: "v1".to_string(),
+        },
+    );
+    let rows = family_ranking_feature_rows(&gold_names, &feature_extractor, &name_divider_base);
+    // 原敬 is divided by rule and takes no group.
+    let groups: Vec<usize> = rows.iter().map(|row| row.group_id).collect();
+    assert_eq!(groups, vec![0, 0, 1, 1, 1, 1]);
+    let gold: Vec<bool> = rows.iter().map(|row| row.is_gold).collect();
+    assert_eq!(gold, vec![true, false, false, false, true, false]);
+    assert_eq!(
+        (rows[4].family.as_str(), rows[4].given.as_str()),
+        ("中曽根", "康弘")
+    );
+    // Compared as text since a missing rank is NaN.
+    assert_eq!(
+        format!("{:?}", rows[4].features),
+        format!(
+            "{:?}",
+            feature_extractor.get_features("中曽根", "康弘").to_vec()
+        )
+    );
+    assert_eq!(rows[0].features.len(), FAMILY_RANKING_FEATURE_NAMES.len());
+}
+
+#[test]
+fn validation_split() {
+    let corpus: String = (0..1000).map(|i| format!("名{} 前\n", i)).collect();
+    let (training_names, validation_names) =
+        split_validation_names(parse_divided_names(&corpus, " "), 0.2, 7);
+    assert_eq!(training_names.len() + validation_names.len(), 1000);
+    assert!((150..250).contains(&validation_names.len()));
+
+    // A name stays on its side whatever else is in the corpus.
+    let (_, validation_subset) = split_validation_names(
+        parse_divided_names(&corpus[..corpus.len() / 2], " "),
+        0.2,
+        7,
+    );
+    assert!(validation_subset.iter().all(|gold_name| validation_names
+        .iter()
+        .any(|v| v.family == gold_name.family)));
+
+    let (training_names, _) = split_validation_names(parse_divided_names(&corpus, " "), 0.0, 7);
+    assert_eq!(training_names.len(), 1000);
+}
+
+#[test]
+fn trainer_params() {
+    let mut config = GBDTTrainerConfig::default();
+    config
+        .extra_params
+        .insert("num_leaves".to_string(), 15.into());
+    config
+        .extra_params
+        .insert("lambda_l2".to_string(), 1.0.into());
+    let params = config.to_params();
+    assert_eq!(params["objective"], "binary");
+    assert_eq!(params["num_iterations"], 300);
+    assert_eq!(params["num_leaves"], 15);
+    assert_eq!(params["lambda_l2"], 1.0);
+}
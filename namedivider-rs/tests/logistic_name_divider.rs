@@ -0,0 +1,69 @@
+use namedivider_rs::corpus::parse_divided_names;
+use namedivider_rs::divider::logistic_name_divider::get_logistic_name_divider;
+use namedivider_rs::divider::logistic_score_calculator::{to_logistic_features, LogisticModel};
+use namedivider_rs::divider::logistic_trainer::{train_logistic_model, LogisticTrainerConfig};
+use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::name_divider_base::NameDividerBase;
+use namedivider_rs::divider::provenance::Provenance;
+use namedivider_rs::feature::extractor::FamilyRankingFeatureExtractor;
+
+fn train_model() -> LogisticModel {
+    let gold_names = parse_divided_names("菅 義偉\n中曽根 康弘\n田中 太郎\n竈門 炭治郎\n", " ");
+    let feature_extractor = FamilyRankingFeatureExtractor::shared();
+    let name_divider_base = NameDividerBase::new(
+        " ".to_string(),
+        true,
+        Provenance::Model {
+            name: "logistic".to_string(),
+            version: None,
+        },
+    );
+    train_logistic_model(
+        &gold_names,
+        &feature_extractor,
+        &name_divider_base,
+        &LogisticTrainerConfig::default(),
+    )
+}
+
+#[test]
+fn model_json_round_trip() {
+    let model = train_model();
+    let restored = LogisticModel::from_json(&model.to_json()).unwrap();
+    for (restored_weight, weight) in restored.weights.iter().zip(&model.weights) {
+        assert!((restored_weight - weight).abs() < 1e-12);
+    }
+    assert!((restored.bias - model.bias).abs() < 1e-12);
+    assert_eq!(restored.missing_rank, model.missing_rank);
+    assert!(LogisticModel::from_json(r#"{"feature_names": [], "use_extra_features": true, "missing_rank": 1.0, "means": [], "scales": [], "weights": [], "bias": 0.0}"#).is_err());
+}
+
+#[test]
+fn divide_with_feature() {
+    let undivided_name = "菅義偉".to_string();
+    let divider = get_logistic_name_divider(" ".to_string(), true, train_model());
+    let divided_name = divider.divide_name(&undivided_name);
+    assert_eq!(divided_name.family, "菅".to_string());
+    assert_eq!(divided_name.given, "義偉".to_string());
+    assert_eq!(divided_name.separator, " ".to_string());
+    assert_eq!(divided_name.algorithm(), "logistic".to_string());
+}
+
+#[test]
+fn missing_rank_is_beyond_last_rank() {
+    let feature_extractor = FamilyRankingFeatureExtractor::shared();
+    let missing_rank = train_model().missing_rank;
+    assert_eq!(
+        missing_rank,
+        feature_extractor.family_name_repository.len() as f64
+    );
+    let known = feature_extractor.get_features("佐藤", "太郎");
+    let unknown = feature_extractor.get_features("佐藤太", "郎");
+    assert!(unknown.rank.is_nan());
+    for use_extra_features in [false, true] {
+        let known_values = to_logistic_features(&known, missing_rank, use_extra_features);
+        let unknown_values = to_logistic_features(&unknown, missing_rank, use_extra_features);
+        assert_eq!(unknown_values[0], missing_rank);
+        assert!(unknown_values[0] > known_values[0]);
+    }
+}
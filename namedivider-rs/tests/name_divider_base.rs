@@ -0,0 +1,58 @@
+use namedivider_rs::divider::name_divider_base::NameDividerBase;
+use namedivider_rs::divider::provenance::Provenance;
+use namedivider_rs::divider::score_calculator::ScoreCalculator;
+
+/// Scores each split by the length of the family name times `scale`.
+struct FamilyLengthScoreCalculator {
+    scale: f64,
+}
+
+impl ScoreCalculator for FamilyLengthScoreCalculator {
+    fn calc_score(&self, family: &str, _given: &str) -> f64 {
+        family.chars().count() as f64 * self.scale
+    }
+}
+
+fn name_divider_base() -> NameDividerBase {
+    NameDividerBase::new(" ".to_string(), true, Provenance::Basic)
+}
+
+#[test]
+fn fallback_takes_the_highest_score() {
+    let name_divider_base = name_divider_base();
+    let undivided_name = "田中太郎".to_string();
+    for scale in [1000.0, -1000.0] {
+        let score_calculator = FamilyLengthScoreCalculator { scale };
+        let divided_name = name_divider_base.divide_name(&undivided_name, &score_calculator);
+        let candidates =
+            name_divider_base.divide_name_candidates(&undivided_name, &score_calculator);
+        assert_eq!(divided_name.provenance, Provenance::Fallback);
+        assert_eq!(divided_name.score, 0.0);
+        assert_eq!(
+            divided_name.family,
+            if scale > 0.0 { "田中太" } else { "田" }.to_string()
+        );
+        assert_eq!(candidates[0].family, divided_name.family);
+        assert!(candidates
+            .iter()
+            .all(|candidate| candidate.provenance == Provenance::Fallback));
+    }
+
+    let score_calculator = FamilyLengthScoreCalculator { scale: f64::NAN };
+    let divided_name = name_divider_base.divide_name(&undivided_name, &score_calculator);
+    assert_eq!(divided_name.provenance, Provenance::Fallback);
+    assert_eq!(divided_name.family, "田".to_string());
+}
+
+#[test]
+fn candidates_follow_divide_name() {
+    let name_divider_base = name_divider_base();
+    let score_calculator = FamilyLengthScoreCalculator { scale: 1.0 };
+    let undivided_name = "田中太郎".to_string();
+    let divided_name = name_divider_base.divide_name(&undivided_name, &score_calculator);
+    let candidates = name_divider_base.divide_name_candidates(&undivided_name, &score_calculator);
+    assert_eq!(divided_name.provenance, Provenance::Basic);
+    assert_eq!(candidates[0].family, divided_name.family);
+    assert_eq!(candidates[0].score, divided_name.score);
+    assert_eq!(candidates[2].family, "田".to_string());
+}
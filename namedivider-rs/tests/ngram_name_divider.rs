@@ -0,0 +1,17 @@
+use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::ngram_name_divider::get_ngram_name_divider;
+use namedivider_rs::feature::ngram::NgramStatisticsBuilder;
+
+#[test]
+fn divide_with_ngram() {
+    let mut builder = NgramStatisticsBuilder::new();
+    builder.add("田中", "太郎");
+    builder.add("山田", "太一");
+    builder.add("中田", "一郎");
+    let divider = get_ngram_name_divider(" ".to_string(), true, builder.build(1));
+    let divided_name = divider.divide_name(&"田中一郎".to_string());
+    assert_eq!(divided_name.family, "田中".to_string());
+    assert_eq!(divided_name.given, "一郎".to_string());
+    assert_eq!(divided_name.separator, " ".to_string());
+    assert_eq!(divided_name.algorithm(), "ngram".to_string());
+}
@@ -0,0 +1,48 @@
+use namedivider_rs::divider::basic_name_divider::BasicNameDivider;
+use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::parity::{parse_reference_division, ParityReportBuilder};
+
+#[test]
+fn parse_reference() {
+    let reference =
+        parse_reference_division(r#"{"family": "菅", "given": "義偉", "score": 0.5}"#).unwrap();
+    assert_eq!(reference.undivided_name(), "菅義偉");
+    assert_eq!(reference.algorithm, None);
+
+    let reference = parse_reference_division(
+        r#"{"name": "菅 義偉", "family": "菅", "given": "義偉", "score": 0.5, "algorithm": "kanji_feature"}"#,
+    )
+    .unwrap();
+    assert_eq!(reference.undivided_name(), "菅 義偉");
+    assert_eq!(reference.algorithm, Some("kanji_feature".to_string()));
+
+    assert!(parse_reference_division(r#"{"family": "菅", "given": "義偉"}"#).is_err());
+    assert!(
+        parse_reference_division(r#"{"family": "", "given": "菅義偉", "score": 0.5}"#).is_err()
+    );
+}
+
+#[test]
+fn parity_report() {
+    let divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let mut builder = ParityReportBuilder::new(0.001);
+    for line in [
+        r#"{"family": "原", "given": "敬", "score": 1.0, "algorithm": "rule"}"#,
+        r#"{"family": "菅義", "given": "偉", "score": 0.9, "algorithm": "kanji_feature"}"#,
+        r#"{"family": "中山", "given": "マサ", "score": 1.0}"#,
+    ] {
+        let reference = parse_reference_division(line).unwrap();
+        let divided_name = divider.divide_name(&reference.undivided_name());
+        builder.add(&reference, &divided_name);
+    }
+    let report = builder.build();
+    assert_eq!(report.num_names, 3);
+    assert_eq!(report.num_same_split, 2);
+    assert_eq!(report.num_with_algorithm, 2);
+    assert_eq!(report.num_same_algorithm, 2);
+    assert_eq!(report.num_within_tolerance, 2);
+    assert_eq!(report.max_abs_score_delta, 0.0);
+    assert_eq!(report.differences.len(), 1);
+    assert_eq!(report.differences[0].name, "菅義偉");
+    assert!(!report.differences[0].is_same_split);
+}
@@ -0,0 +1,44 @@
+use namedivider_rs::divider::basic_name_divider::BasicNameDivider;
+use namedivider_rs::divider::gbdt_name_divider::GBDTNameDivider;
+use std::sync::Arc;
+
+#[test]
+fn dividers_share_repositories() {
+    let basic_divider = BasicNameDivider::new(" ".to_string(), true, false);
+    let other_basic_divider = BasicNameDivider::new("・".to_string(), false, true);
+    let gbdt_divider = GBDTNameDivider::new(" ".to_string(), true);
+    let other_gbdt_divider = GBDTNameDivider::new("/".to_string(), false);
+
+    let kanji_statistics_repository = &basic_divider
+        .basic_score_calculator
+        .feature_extractor
+        .kanji_statistics_repository;
+    assert!(Arc::ptr_eq(
+        kanji_statistics_repository,
+        &other_basic_divider
+            .basic_score_calculator
+            .feature_extractor
+            .kanji_statistics_repository
+    ));
+    assert!(Arc::ptr_eq(
+        kanji_statistics_repository,
+        &gbdt_divider
+            .gbdt_score_calculator
+            .feature_extractor
+            .kanji_statistics_repository
+    ));
+    assert!(Arc::ptr_eq(
+        &gbdt_divider
+            .gbdt_score_calculator
+            .feature_extractor
+            .family_name_repository,
+        &other_gbdt_divider
+            .gbdt_score_calculator
+            .feature_extractor
+            .family_name_repository
+    ));
+    assert!(Arc::ptr_eq(
+        &gbdt_divider.gbdt_score_calculator.model_string,
+        &other_gbdt_divider.gbdt_score_calculator.model_string
+    ));
+}
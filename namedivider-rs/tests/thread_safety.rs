@@ -1,4 +1,4 @@
-use namedivider_rs::divider::gbdt_name_divider::get_gbdt_name_divider;
+use namedivider_rs::divider::gbdt_name_divider::GBDTNameDivider;
 use namedivider_rs::divider::name_divider::NameDivider;
 use std::sync::Arc;
 use std::thread;
@@ -20,7 +20,7 @@ const TEST_NAMES: &[&str] = &[
 #[test]
 fn test_gbdt_name_divider_single_thread() {
     // 単一スレッドでの正常動作確認
-    let divider = get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string());
+    let divider = GBDTNameDivider::new(" ".to_string(), true);
     
     for name in TEST_NAMES {
         let divided_name = divider.divide_name(&name.to_string());
@@ -45,7 +45,7 @@ fn test_gbdt_name_divider_multi_thread_concurrent_access() {
     // 注意: このテストを有効化するとコンパイルエラーが発生します
     // 詳細は上記のコメントを参照
     
-    // let divider = Arc::new(get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string()));
+    // let divider = Arc::new(GBDTNameDivider::new(" ".to_string(), true));
     // let mut handles = vec![];
     
     // for thread_id in 0..10 {
@@ -70,7 +70,7 @@ fn test_gbdt_name_divider_separate_instances() {
     for thread_id in 0..5 {
         let handle = thread::spawn(move || {
             // 各スレッドで独立したdividerインスタンスを作成
-            let divider = get_gbdt_name_divider(" ".to_string(), true, "gbdt".to_string());
+            let divider = GBDTNameDivider::new(" ".to_string(), true);
             
             for i in 0..50 {
                 let name_index = (thread_id * 50 + i) % TEST_NAMES.len();
@@ -1,11 +1,12 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
-use namedivider_rs::divider::basic_name_divider::get_basic_name_divider;
 use namedivider_rs::divider::basic_name_divider::BasicNameDivider;
-use namedivider_rs::divider::gbdt_name_divider::get_gbdt_name_divider;
+use namedivider_rs::divider::divided_name::{DividedName, Span};
 use namedivider_rs::divider::gbdt_name_divider::GBDTNameDivider;
 use namedivider_rs::divider::name_divider::NameDivider;
+use namedivider_rs::divider::provenance::Provenance;
 use namedivider_rs::divider::score_calculator::ScoreCalculator;
 
 #[pyclass(name = "DividedName")]
@@ -15,6 +16,36 @@ struct PyDividedName {
     separator: String,
     algorithm: String,
     score: f64,
+    provenance: Provenance,
+    normalized_family: String,
+    normalized_given: String,
+    family_span: Span,
+    given_span: Span,
+}
+
+impl PyDividedName {
+    fn from_divided_name(divided_name: DividedName) -> Self {
+        Self {
+            algorithm: divided_name.algorithm(),
+            family: divided_name.family,
+            given: divided_name.given,
+            separator: divided_name.separator,
+            score: divided_name.score,
+            provenance: divided_name.provenance,
+            normalized_family: divided_name.normalized_family,
+            normalized_given: divided_name.normalized_given,
+            family_span: divided_name.family_span,
+            given_span: divided_name.given_span,
+        }
+    }
+
+    fn provenance_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        for (key, value) in self.provenance.attributes() {
+            dict.set_item(key, value)?;
+        }
+        Ok(dict)
+    }
 }
 
 #[pymethods]
@@ -44,6 +75,45 @@ impl PyDividedName {
         Ok(self.score)
     }
 
+    #[getter]
+    fn normalized_family(&self) -> PyResult<String> {
+        Ok(self.normalized_family.clone())
+    }
+
+    #[getter]
+    fn normalized_given(&self) -> PyResult<String> {
+        Ok(self.normalized_given.clone())
+    }
+
+    /// (start, end) of the family name in the input, in characters.
+    #[getter]
+    fn family_span(&self) -> PyResult<(usize, usize)> {
+        Ok((self.family_span.char_start, self.family_span.char_end))
+    }
+
+    /// (start, end) of the given name in the input, in characters.
+    #[getter]
+    fn given_span(&self) -> PyResult<(usize, usize)> {
+        Ok((self.given_span.char_start, self.given_span.char_end))
+    }
+
+    /// (start, end) of the family name in the UTF-8 encoded input.
+    #[getter]
+    fn family_byte_span(&self) -> PyResult<(usize, usize)> {
+        Ok((self.family_span.byte_start, self.family_span.byte_end))
+    }
+
+    /// (start, end) of the given name in the UTF-8 encoded input.
+    #[getter]
+    fn given_byte_span(&self) -> PyResult<(usize, usize)> {
+        Ok((self.given_span.byte_start, self.given_span.byte_end))
+    }
+
+    #[getter]
+    fn provenance(&self, py: Python<'_>) -> PyResult<Py<PyDict>> {
+        Ok(self.provenance_dict(py)?.unbind())
+    }
+
     fn __str__(&self) -> PyResult<String> {
         Ok(self.family.clone() + &self.separator + &self.given)
     }
@@ -55,6 +125,7 @@ impl PyDividedName {
         dict.set_item("separator", &self.separator)?;
         dict.set_item("algorithm", &self.algorithm)?;
         dict.set_item("score", self.score)?;
+        dict.set_item("provenance", self.provenance_dict(py)?)?;
         Ok(dict.unbind())
     }
 }
@@ -69,10 +140,9 @@ impl PyBasicNameDivider {
     #[new]
     #[pyo3(signature = (separator = " ", normalize_name = true, only_order_score_when_4 = false))]
     fn new(separator: &str, normalize_name: bool, only_order_score_when_4: bool) -> Self {
-        let divider = get_basic_name_divider(
+        let divider = BasicNameDivider::new(
             separator.to_string(),
             normalize_name,
-            "kanji_feature".to_string(),
             only_order_score_when_4,
         );
         Self { divider }
@@ -86,27 +156,21 @@ impl PyBasicNameDivider {
     }
 
     fn divide_name(&self, undivided_name: String) -> PyResult<PyDividedName> {
-        let divided_name = self.divider.divide_name(&undivided_name);
-        Ok(PyDividedName {
-            family: divided_name.family,
-            given: divided_name.given,
-            separator: divided_name.separator,
-            algorithm: divided_name.algorithm,
-            score: divided_name.score,
-        })
+        let divided_name = self
+            .divider
+            .try_divide_name(&undivided_name)
+            .map_err(PyValueError::new_err)?;
+        Ok(PyDividedName::from_divided_name(divided_name))
     }
 
     fn divide_names(&self, undivided_names: Vec<String>) -> PyResult<Vec<PyDividedName>> {
         let mut results = Vec::new();
         for undivided_name in undivided_names {
-            let divided_name = self.divider.divide_name(&undivided_name);
-            results.push(PyDividedName {
-                family: divided_name.family,
-                given: divided_name.given,
-                separator: divided_name.separator,
-                algorithm: divided_name.algorithm,
-                score: divided_name.score,
-            });
+            let divided_name = self
+                .divider
+                .try_divide_name(&undivided_name)
+                .map_err(PyValueError::new_err)?;
+            results.push(PyDividedName::from_divided_name(divided_name));
         }
         Ok(results)
     }
@@ -122,8 +186,7 @@ impl PyGBDTNameDivider {
     #[new]
     #[pyo3(signature = (separator = " ", normalize_name = true))]
     fn new(separator: &str, normalize_name: bool) -> Self {
-        let divider =
-            get_gbdt_name_divider(separator.to_string(), normalize_name, "gbdt".to_string());
+        let divider = GBDTNameDivider::new(separator.to_string(), normalize_name);
         Self { divider }
     }
 
@@ -135,27 +198,21 @@ impl PyGBDTNameDivider {
     }
 
     fn divide_name(&self, undivided_name: String) -> PyResult<PyDividedName> {
-        let divided_name = self.divider.divide_name(&undivided_name);
-        Ok(PyDividedName {
-            family: divided_name.family,
-            given: divided_name.given,
-            separator: divided_name.separator,
-            algorithm: divided_name.algorithm,
-            score: divided_name.score,
-        })
+        let divided_name = self
+            .divider
+            .try_divide_name(&undivided_name)
+            .map_err(PyValueError::new_err)?;
+        Ok(PyDividedName::from_divided_name(divided_name))
     }
 
     fn divide_names(&self, undivided_names: Vec<String>) -> PyResult<Vec<PyDividedName>> {
         let mut results = Vec::new();
         for undivided_name in undivided_names {
-            let divided_name = self.divider.divide_name(&undivided_name);
-            results.push(PyDividedName {
-                family: divided_name.family,
-                given: divided_name.given,
-                separator: divided_name.separator,
-                algorithm: divided_name.algorithm,
-                score: divided_name.score,
-            });
+            let divided_name = self
+                .divider
+                .try_divide_name(&undivided_name)
+                .map_err(PyValueError::new_err)?;
+            results.push(PyDividedName::from_divided_name(divided_name));
         }
         Ok(results)
     }